@@ -1,3 +1,4 @@
+use crate::unit_db::DEFAULT_LANGUAGE;
 use configparser::ini;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -23,12 +24,15 @@ pub struct ConfigWriteError {
 pub struct Config {
     /// The path where Sea Power is located.
     pub game_root: PathBuf,
+    /// The language code unit names are displayed in, e.g. `en` or `ru`.
+    pub language: String,
 }
 
 impl Config {
     pub fn new<P: AsRef<Path>>(game_root: P) -> Self {
         Config {
             game_root: game_root.as_ref().to_path_buf(),
+            language: DEFAULT_LANGUAGE.to_owned(),
         }
     }
 
@@ -38,7 +42,14 @@ impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigLoadError> {
         let config = load_config(path.as_ref())?;
         let game_root = fetch_key(&config, "general", "game_root")?.into();
-        Ok(Config { game_root })
+        // optional, older configs won't have it
+        let language = config
+            .get("general", "language")
+            .unwrap_or_else(|| DEFAULT_LANGUAGE.to_owned());
+        Ok(Config {
+            game_root,
+            language,
+        })
     }
 
     /// Save state to a config file at the given `path`.
@@ -49,6 +60,7 @@ impl Config {
             let game_root = self.game_root.as_path().to_str().unwrap();
             ini.setstr("general", "game_root", Some(game_root));
         }
+        ini.setstr("general", "language", Some(&self.language));
         ini.write(&path).map_err(|e| ConfigWriteError {
             path: path.as_ref().to_owned(),
             source: e,
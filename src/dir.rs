@@ -5,8 +5,12 @@ use std::{
 
 const MISSION_DIR: &str = r"Sea Power_Data\StreamingAssets\user\missions";
 const ORIGINAL_DIR: &str = r"Sea Power_Data\StreamingAssets\original";
+const USER_DIR: &str = r"Sea Power_Data\StreamingAssets\user";
+const MODS_DIR: &str = r"Sea Power_Data\StreamingAssets\mods";
 const AIRCRAFT_DIR: &str = "aircraft";
 const VESSEL_DIR: &str = "vessels";
+const LAND_UNIT_DIR: &str = "land_units";
+const LANGUAGE_DIR_PREFIX: &str = "language_";
 
 pub fn config_dir() -> PathBuf {
     // very unlikely to fail (unless you're running something older than
@@ -34,12 +38,40 @@ pub fn original_dir(root_dir: &Path) -> PathBuf {
     root_dir.join(ORIGINAL_DIR)
 }
 
-pub fn aircraft_dir(root_dir: &Path) -> PathBuf {
-    original_dir(root_dir).join(AIRCRAFT_DIR)
+pub fn user_dir(root_dir: &Path) -> PathBuf {
+    root_dir.join(USER_DIR)
 }
 
-pub fn vessel_dir(root_dir: &Path) -> PathBuf {
-    original_dir(root_dir).join(VESSEL_DIR)
+/// The directory containing every mod, each in their own subdirectory.
+pub fn mods_dir(root_dir: &Path) -> PathBuf {
+    root_dir.join(MODS_DIR)
+}
+
+// The following take a data directory, which is either `original_dir` or a
+// directory overlaid on top of it (user or mod).
+
+pub fn aircraft_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(AIRCRAFT_DIR)
+}
+
+pub fn vessel_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(VESSEL_DIR)
+}
+
+pub fn land_unit_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(LAND_UNIT_DIR)
+}
+
+pub fn language_dir(data_dir: &Path, language: &str) -> PathBuf {
+    data_dir.join(format!("{LANGUAGE_DIR_PREFIX}{language}"))
+}
+
+/// Extract the language code from a language directory name, e.g.
+/// `language_en` => `en`.
+pub fn language_code(dir_name: &str) -> Option<&str> {
+    dir_name
+        .strip_prefix(LANGUAGE_DIR_PREFIX)
+        .filter(|code| !code.is_empty())
 }
 
 fn check_known_locations() -> Option<PathBuf> {
@@ -2,16 +2,25 @@ mod reusable_id;
 mod views;
 
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
-use crate::mission::{self, MissionOptions, TaskforceOptions, UnitOption};
-use crate::unit_db::{Nation, Unit, UnitDb, UnitType};
+use crate::dir;
+use crate::mission::{
+    self, CrewSkill, Damage, FormationLayout, FormationShape, ImportReport, MissionOptions,
+    MissionType, Problem, RouteStyle, SpawnPositions, StationRole, StoresOption, TaskforceOptions,
+    UnitOption, UnitSettings, VariantOption,
+};
+use crate::rand_ext;
+use crate::terrain::Terrain;
+use crate::unit_db::{LoadReport, Nation, SizeClass, Unit, UnitDb, UnitQuery, UnitType};
 
 use cursive::event::{self, Event};
 use cursive::reexports::log::LevelFilter;
 use cursive::traits::*;
 use cursive::views::{
-    Button, Dialog, EditView, LinearLayout, ListView, OnEventView, Panel, SelectView, TextView,
+    Button, Checkbox, Dialog, EditView, LinearLayout, ListView, OnEventView, Panel, SelectView,
+    TextView,
 };
 use cursive::Cursive;
 
@@ -83,30 +92,54 @@ pub fn ask_for_game_path(show_error: bool) -> AskForGamePathCommand {
 
 #[derive(Clone, Debug)]
 struct AppState {
-    all_units: Arc<Vec<Unit>>,
+    /// every unit, named in the user's language
+    unit_db: Arc<UnitDb>,
     nations: Arc<Vec<Nation>>,
     mission: Arc<Mutex<MissionOptions>>,
+    load_report: Arc<LoadReport>,
+    terrain: Arc<Terrain>,
 }
 
 pub struct App {
     state: AppState,
+    /// shown on start, if anything couldn't be imported as is
+    import_report: Option<ImportReport>,
 }
 
 impl App {
-    pub fn new(unit_db: &UnitDb) -> Self {
-        let all_units = unit_db.all().into_iter().cloned().collect();
+    /// Create the app, unit names are displayed in the given `language`.
+    pub fn new(unit_db: &UnitDb, language: &str, load_report: LoadReport) -> Self {
         let nations = unit_db.nations().into_iter().cloned().collect();
         let state = AppState {
-            all_units: Arc::new(all_units),
+            unit_db: Arc::new(unit_db.localised(language)),
             nations: Arc::new(nations),
             mission: Arc::new(Mutex::new(MissionOptions::default())),
+            load_report: Arc::new(load_report),
+            terrain: Arc::new(Terrain::default()),
         };
-        Self { state }
+        Self {
+            state,
+            import_report: None,
+        }
+    }
+
+    /// Start from the options of an imported mission instead of the
+    /// defaults, along with what couldn't be imported.
+    pub fn with_import(mut self, options: MissionOptions, report: ImportReport) -> Self {
+        *self.state.mission.lock().unwrap() = options;
+        self.import_report = Some(report);
+        self
+    }
+
+    /// Warn when the mission area is mostly land in the given `terrain`.
+    pub fn with_terrain(mut self, terrain: Arc<Terrain>) -> Self {
+        self.state.terrain = terrain;
+        self
     }
 
     pub fn run<F>(self, on_submit: F)
     where
-        F: Fn(MissionOptions) + Send + Sync + 'static,
+        F: Fn(MissionOptions) -> Vec<Problem> + Send + Sync + 'static,
     {
         cursive::logger::init();
         // turn off internal cursive logging
@@ -117,47 +150,182 @@ impl App {
         siv.add_global_callback('`', Cursive::toggle_debug_console);
 
         siv.add_layer(main_view(self.state, on_submit));
+        if let Some(report) = self.import_report.filter(|r| !r.problems.is_empty()) {
+            siv.add_layer(import_report_view(&report));
+        }
         siv.run();
     }
 }
 
 fn main_view<F>(state: AppState, on_submit: F) -> impl View
 where
-    F: Fn(MissionOptions) + Send + Sync + 'static,
+    F: Fn(MissionOptions) -> Vec<Problem> + Send + Sync + 'static,
 {
+    let general = state.mission.lock().unwrap().general.clone();
+    let date = general.date;
+    let field = |content: String, name: &str| {
+        EditView::new()
+            .content(content)
+            .with_name(name)
+            .fixed_width(6)
+    };
+    let optional_field = |value: Option<f32>, name: &str| {
+        field(value.map(|v| v.to_string()).unwrap_or_default(), name)
+    };
+    let placement_select = |fetcher: fn(&MissionOptions) -> &TaskforceOptions| {
+        let current = fetcher(&state.mission.lock().unwrap()).placement;
+        let mut view = SelectView::new().popup();
+        view.add_all(
+            mission::PlacementStrategy::all()
+                .into_iter()
+                .map(|p| (p.to_string(), p)),
+        );
+        let idx = view.iter().position(|(_, p)| *p == current);
+        if let Some(idx) = idx {
+            view.set_selection(idx);
+        }
+        view
+    };
+    let separation = &general.separation;
     let general_form = ListView::new()
         .child(
             "Latitude/Longitude",
             LinearLayout::horizontal()
-                .child(EditView::new().with_name("latitude").fixed_width(6))
+                .child(field(general.latlon.0.to_string(), "latitude"))
                 .child(TextView::new(","))
-                .child(EditView::new().with_name("longitude").fixed_width(6)),
+                .child(field(general.latlon.1.to_string(), "longitude")),
         )
         .child(
             "Width/Height (nm)",
             LinearLayout::horizontal()
-                .child(EditView::new().with_name("size_w").fixed_width(6))
+                .child(field(general.size.0.to_string(), "size_w"))
                 .child(TextView::new(","))
-                .child(EditView::new().with_name("size_h").fixed_width(6)),
+                .child(field(general.size.1.to_string(), "size_h")),
+        )
+        .child(
+            "Date (Y,M,D)",
+            EditView::new()
+                .content(date.to_string())
+                .with_name("date")
+                .fixed_width(10),
+        )
+        .child(
+            "Land Offset (nm)",
+            LinearLayout::horizontal()
+                .child(EditView::new().with_name("land_x").fixed_width(6))
+                .child(TextView::new(","))
+                .child(EditView::new().with_name("land_y").fixed_width(6)),
+        )
+        .child(
+            "Land Width/Height",
+            LinearLayout::horizontal()
+                .child(EditView::new().with_name("land_w").fixed_width(6))
+                .child(TextView::new(","))
+                .child(EditView::new().with_name("land_h").fixed_width(6)),
+        )
+        .child(
+            "Min Spacing (nm)",
+            optional_field(separation.min_spacing, "min_spacing"),
+        )
+        .child(
+            "Enemy Range Min/Max",
+            LinearLayout::horizontal()
+                .child(optional_field(
+                    separation.min_enemy_range,
+                    "min_enemy_range",
+                ))
+                .child(TextView::new(","))
+                .child(optional_field(
+                    separation.max_enemy_range,
+                    "max_enemy_range",
+                )),
+        )
+        .child(
+            "Neutral Placement",
+            placement_select(|m| &m.neutral).with_name("neutral_placement"),
+        )
+        .child(
+            "Blue Placement",
+            placement_select(|m| &m.blue).with_name("blue_placement"),
+        )
+        .child(
+            "Red Placement",
+            placement_select(|m| &m.red).with_name("red_placement"),
+        )
+        .child(
+            "Seed (blank = random)",
+            EditView::new().with_name("seed").fixed_width(20),
         );
 
+    let environment = state.mission.lock().unwrap().environment.clone();
+    let or_blank = |value: Option<String>| value.unwrap_or_default();
+    let environment_form = ListView::new()
+        .child(
+            "Random Day",
+            Checkbox::new()
+                .with_checked(environment.random_day)
+                .with_name("random_day"),
+        )
+        .child(
+            "Time (H,M)",
+            EditView::new()
+                .content(or_blank(environment.time.map(|t| t.to_string())))
+                .with_name("time")
+                .fixed_width(6),
+        )
+        .child(
+            "Sea State (0-9)",
+            EditView::new()
+                .content(or_blank(environment.sea_state.map(|s| s.to_string())))
+                .with_name("sea_state")
+                .fixed_width(6),
+        )
+        .child(
+            "Clouds",
+            DefaultSelectView::new("<RANDOM>")
+                .with_all(mission::Clouds::all().into_iter())
+                .selected(environment.clouds.as_ref())
+                .popup()
+                .with_name("clouds"),
+        )
+        .child(
+            "Wind Direction",
+            DefaultSelectView::new("<RANDOM>")
+                .with_all(mission::WindDirection::all().into_iter())
+                .selected(environment.wind_direction.as_ref())
+                .popup()
+                .with_name("wind_direction"),
+        )
+        .child("", TextView::new("blank fields are picked at random"));
+
     let neutral_form = {
         let state = state.clone();
-        ListView::new().child(
-            "Unit Groups",
-            Button::new("Customise...", {
-                let state = state.clone();
-                move |s| {
-                    let mission = state.mission.lock().unwrap();
-                    let view = customise_group_view(
-                        &state,
-                        &mission.neutral,
-                        fill_taskforce(state.mission.clone(), |m| &mut m.neutral),
-                    );
-                    s.add_layer(view);
-                }
-            }),
-        )
+        ListView::new()
+            .child(
+                "Unit Groups",
+                Button::new("Customise...", {
+                    let state = state.clone();
+                    move |s| {
+                        let year = mission_year(s);
+                        let mission = state.mission.lock().unwrap();
+                        let view = customise_group_view(
+                            &state,
+                            &mission.neutral,
+                            year,
+                            fill_taskforce(state.mission.clone(), |m| &mut m.neutral),
+                        );
+                        s.add_layer(view);
+                    }
+                }),
+            )
+            .child(
+                "Unit Settings",
+                taskforce_settings_button(state.mission.clone(), |m| &mut m.neutral),
+            )
+            .child(
+                "Route",
+                taskforce_route_select(state.mission.clone(), |m| &mut m.neutral),
+            )
     };
 
     let blue_form = ListView::new()
@@ -167,15 +335,25 @@ where
             Button::new("Customise...", {
                 let state = state.clone();
                 move |s| {
+                    let year = mission_year(s);
                     let mission = state.mission.lock().unwrap();
                     let view = customise_group_view(
                         &state,
                         &mission.blue,
+                        year,
                         fill_taskforce(state.mission.clone(), |m| &mut m.blue),
                     );
                     s.add_layer(view);
                 }
             }),
+        )
+        .child(
+            "Unit Settings",
+            taskforce_settings_button(state.mission.clone(), |m| &mut m.blue),
+        )
+        .child(
+            "Route",
+            taskforce_route_select(state.mission.clone(), |m| &mut m.blue),
         );
 
     let red_form = ListView::new()
@@ -185,57 +363,208 @@ where
             Button::new("Customise...", {
                 let state = state.clone();
                 move |s| {
+                    let year = mission_year(s);
                     let mission = state.mission.lock().unwrap();
                     let view = customise_group_view(
                         &state,
                         &mission.red,
+                        year,
                         fill_taskforce(state.mission.clone(), |m| &mut m.red),
                     );
                     s.add_layer(view);
                 }
             }),
+        )
+        .child(
+            "Unit Settings",
+            taskforce_settings_button(state.mission.clone(), |m| &mut m.red),
+        )
+        .child(
+            "Route",
+            taskforce_route_select(state.mission.clone(), |m| &mut m.red),
         );
 
+    let report_form = LinearLayout::horizontal()
+        .child(TextView::new(format!(
+            "{} unit files could not be loaded ",
+            state.load_report.skipped.len()
+        )))
+        .child(Button::new("Save Report", {
+            let report = state.load_report.clone();
+            move |s| save_load_report(s, &report)
+        }));
+
     Dialog::new()
         .title("Create Mission")
         .button("Generate", {
+            let on_submit = Arc::new(on_submit);
             move |s| {
                 let mut mission = state.mission.lock().unwrap();
-                fill_mission(s, &mut mission);
-                on_submit(mission.clone());
-                // TODO: show info on where it was generated
-                s.add_layer(Dialog::info("Mission generated!"));
+                if let Err(errors) = fill_mission(s, &mut mission) {
+                    s.add_layer(Dialog::info(errors.join("\n")).title("Invalid Options"));
+                    return;
+                }
+                let problems = mission.validate(state.unit_db.all(), &state.terrain);
+                let options = mission.clone();
+                drop(mission);
+
+                let text = problems
+                    .iter()
+                    .map(|problem| match problem.is_error() {
+                        true => format!("Error: {problem}"),
+                        false => format!("Warning: {problem}"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if problems.iter().any(Problem::is_error) {
+                    s.add_layer(Dialog::info(text).title("Unable to Generate"));
+                } else if problems.is_empty() {
+                    generate(s, options, on_submit.as_ref());
+                } else {
+                    let on_submit = on_submit.clone();
+                    s.add_layer(
+                        Dialog::text(text)
+                            .title("Warnings")
+                            .button("Generate Anyway", move |s| {
+                                s.pop_layer();
+                                generate(s, options.clone(), on_submit.as_ref());
+                            })
+                            .dismiss_button("Cancel"),
+                    );
+                }
             }
         })
         .button("Quit", Cursive::quit)
         .content(
             LinearLayout::vertical()
                 .child(Panel::new(general_form).title("General"))
+                .child(Panel::new(environment_form).title("Environment"))
                 .child(Panel::new(neutral_form).title("Neutral"))
                 .child(Panel::new(blue_form).title("Blue"))
-                .child(Panel::new(red_form).title("Red")),
+                .child(Panel::new(red_form).title("Red"))
+                .child(report_form),
         )
 }
 
+/// Generate the mission and tell the user how to generate it again, along
+/// with any problems found while generating.
+fn generate(
+    s: &mut Cursive,
+    options: MissionOptions,
+    on_submit: &impl Fn(MissionOptions) -> Vec<Problem>,
+) {
+    let seed = options
+        .seed
+        .map(|seed| seed.to_string())
+        .unwrap_or_default();
+    let problems = on_submit(options);
+    // TODO: show info on where it was generated
+    let mut text = format!(
+        "Mission generated with seed {seed}, use it to generate the same \
+         mission again."
+    );
+    for problem in problems {
+        text.push_str(&format!("\nWarning: {problem}"));
+    }
+    s.add_layer(Dialog::info(text));
+}
+
+/// A dialog listing everything in an imported mission that couldn't be
+/// imported as is.
+fn import_report_view(report: &ImportReport) -> impl View {
+    let text = report
+        .problems
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Dialog::around(TextView::new(text).scrollable())
+        .title(format!(
+            "{} problems importing the mission",
+            report.problems.len()
+        ))
+        .dismiss_button("Ok")
+}
+
+/// Write the full load report to the config directory, so that modders can
+/// see why their units are missing.
+fn save_load_report(s: &mut Cursive, report: &LoadReport) {
+    let path = dir::config_dir().join("load_report.txt");
+    let message = match report.write(&path) {
+        Ok(()) => format!("Load report saved to {}", path.display()),
+        Err(e) => format!("Failed to save load report to {}: {e}", path.display()),
+    };
+    s.add_layer(Dialog::info(message));
+}
+
+/// The year of the mission date currently in the UI, if it is valid.
+fn mission_year(s: &mut Cursive) -> Option<u16> {
+    s.call_on_name("date", |view: &mut EditView| view.get_content())
+        .and_then(|date| date.parse::<mission::Date>().ok())
+        .map(|date| date.year)
+}
+
+/// Customise the units of a taskforce, only units in service during `year`
+/// are shown by default.
 fn customise_group_view<F>(
     state: &AppState,
     taskforce: &TaskforceOptions,
+    year: Option<u16>,
     on_submit: F,
 ) -> impl View
 where
     F: Fn(&mut Cursive, views::UnitTreeSelection) + Send + Sync + 'static,
 {
+    /// Add the unit at `row`, or any unit of its class if the available
+    /// units are grouped.
     fn add_selected(s: &mut Cursive, row: usize) {
         let available = s
             .find_name::<UnitTable>("available")
             .expect("missing available view");
         if let Some(item) = available.borrow_item(row) {
+            let unit = if available.is_grouped() {
+                class_option(item)
+            } else {
+                UnitOption::Unit {
+                    unit: Box::new(item.clone()),
+                    variant: VariantOption::Default,
+                    stores: StoresOption::Full,
+                    settings: UnitSettings::default(),
+                }
+            };
+            s.call_on_name("selected", |selected: &mut UnitTree| {
+                selected.add_unit(unit);
+            });
+        }
+    }
+
+    /// Add any unit of the same class as the focused unit.
+    fn add_class(s: &mut Cursive) {
+        let unit = s
+            .find_name::<UnitTable>("available")
+            .expect("missing available view")
+            .selected_item()
+            .map(class_option);
+        if let Some(unit) = unit {
             s.call_on_name("selected", |selected: &mut UnitTree| {
-                selected.add_unit(UnitOption::Unit(item.clone()));
+                selected.add_unit(unit);
             });
         }
     }
 
+    fn class_option(unit: &Unit) -> UnitOption {
+        UnitOption::Random {
+            query: Box::new(UnitQuery::new().class(&unit.class_id)),
+            variant: VariantOption::Default,
+            stores: StoresOption::Full,
+            settings: UnitSettings::default(),
+        }
+    }
+
+    fn toggle_grouped(s: &mut Cursive) {
+        s.call_on_name("available", UnitTable::toggle_grouped);
+    }
+
     fn remove_selected(s: &mut Cursive) {
         s.call_on_name("selected", |selected: &mut UnitTree| {
             if let Some(row) = selected.row() {
@@ -251,17 +580,139 @@ where
     }
 
     fn add_random(s: &mut Cursive, state: AppState) {
-        s.add_layer(random_unit_view(&state, |s, nation, utype, count| {
-            s.call_on_name("selected", |selected: &mut UnitTree| {
-                selected.add_n_units(
-                    UnitOption::Random {
-                        nation: nation.clone(),
-                        utype,
-                    },
-                    count,
-                )
-            });
-        }));
+        s.add_layer(random_unit_view(
+            &state,
+            |s, query, random_variant, count| {
+                let variant = if random_variant {
+                    VariantOption::Random
+                } else {
+                    VariantOption::Default
+                };
+                s.call_on_name("selected", |selected: &mut UnitTree| {
+                    selected.add_n_units(
+                        UnitOption::Random {
+                            query: Box::new(query),
+                            variant,
+                            stores: StoresOption::Full,
+                            settings: UnitSettings::default(),
+                        },
+                        count,
+                    )
+                });
+            },
+        ));
+    }
+
+    fn choose_variant(s: &mut Cursive) {
+        let selected = s
+            .call_on_name("selected", |selected: &mut UnitTree| {
+                selected
+                    .row()
+                    .and_then(|row| selected.borrow_unit(row).map(|unit| (row, unit.clone())))
+            })
+            .flatten();
+        if let Some((row, unit)) = selected {
+            s.add_layer(variant_view(&unit, move |s, variant| {
+                s.call_on_name("selected", |selected: &mut UnitTree| {
+                    selected.set_variant(row, variant)
+                });
+            }));
+        }
+    }
+
+    fn choose_stores(s: &mut Cursive) {
+        let selected = s
+            .call_on_name("selected", |selected: &mut UnitTree| {
+                selected
+                    .row()
+                    .and_then(|row| selected.borrow_unit(row).map(|unit| (row, unit.clone())))
+            })
+            .flatten();
+        if let Some((row, unit)) = selected {
+            s.add_layer(stores_view(&unit, move |s, stores| {
+                s.call_on_name("selected", |selected: &mut UnitTree| {
+                    selected.set_stores(row, stores)
+                });
+            }));
+        }
+    }
+
+    fn choose_settings(s: &mut Cursive) {
+        let selected = s
+            .call_on_name("selected", |selected: &mut UnitTree| {
+                selected
+                    .row()
+                    .and_then(|row| selected.borrow_unit(row).map(|unit| (row, unit.clone())))
+            })
+            .flatten();
+        if let Some((row, unit)) = selected {
+            let view = unit_settings_view(
+                unit.settings(),
+                "<TASKFORCE>",
+                "<TASKFORCE>",
+                move |s, settings| {
+                    s.call_on_name("selected", |selected: &mut UnitTree| {
+                        selected.set_settings(row, settings)
+                    });
+                },
+            );
+            s.add_layer(view);
+        }
+    }
+
+    fn choose_route(s: &mut Cursive) {
+        let selected = s
+            .call_on_name("selected", |selected: &mut UnitTree| {
+                selected
+                    .row()
+                    .and_then(|row| selected.formation_route(row).map(|route| (row, route)))
+            })
+            .flatten();
+        if let Some((row, route)) = selected {
+            s.add_layer(route_view(route, move |s, route| {
+                s.call_on_name("selected", |selected: &mut UnitTree| {
+                    selected.set_route(row, route)
+                });
+            }));
+        }
+    }
+
+    fn edit_formation(s: &mut Cursive) {
+        let selected = s
+            .call_on_name("selected", |selected: &mut UnitTree| {
+                selected.row().and_then(|row| {
+                    selected
+                        .formation_layout(row)
+                        .map(|layout| (row, layout.clone()))
+                })
+            })
+            .flatten();
+        if let Some((row, layout)) = selected {
+            s.add_layer(formation_layout_view(&layout, move |s, layout| {
+                s.call_on_name("selected", |selected: &mut UnitTree| {
+                    selected.set_layout(row, layout)
+                });
+            }));
+        }
+    }
+
+    fn toggle_leader(s: &mut Cursive) {
+        s.call_on_name("selected", |selected: &mut UnitTree| {
+            if let Some(row) = selected.row() {
+                selected.toggle_leader(row);
+            }
+        });
+    }
+
+    fn show_info(s: &mut Cursive) {
+        let unit = s
+            .find_name::<UnitTable>("available")
+            .expect("missing available view")
+            .selected_item()
+            .cloned();
+        if let Some(unit) = unit {
+            s.add_layer(unit_info_view(&unit));
+        }
     }
 
     fn filter<T>(s: &mut Cursive, _item: &Option<T>) {
@@ -281,6 +732,23 @@ where
         });
     }
 
+    fn filter_year(s: &mut Cursive, year: &str, _cursor: usize) {
+        // an empty or partially typed year shows every unit
+        let year = year.trim().parse().ok();
+        s.call_on_name("available", |available: &mut UnitTable| {
+            available.in_service(year)
+        });
+    }
+
+    fn search(s: &mut Cursive) {
+        let query = s
+            .call_on_name("filter_search", |view: &mut TextView| {
+                view.get_content().source().to_owned()
+            })
+            .expect("missing filter_search view");
+        s.add_layer(search_view(&query));
+    }
+
     let filter_panel = Panel::new(
         ListView::new()
             .child(
@@ -300,16 +768,22 @@ where
                     .on_submit(filter)
                     .with_name("filter_utype")
                     .max_width(20),
-            ),
+            )
+            .child(
+                "In Service",
+                EditView::new()
+                    .content(year.map(|y| y.to_string()).unwrap_or_default())
+                    .max_content_width(4)
+                    .on_edit(filter_year)
+                    .fixed_width(5),
+            )
+            .child("Search", TextView::new("").with_name("filter_search")),
     )
     .title("Filters");
 
-    let available_panel = Panel::new(
-        UnitTable::new(state.all_units.to_vec())
-            .on_submit(add_selected)
-            .with_name("available"),
-    )
-    .title("Available");
+    let mut available = UnitTable::new(state.unit_db.clone()).on_submit(add_selected);
+    available.in_service(year);
+    let available_panel = Panel::new(available.with_name("available")).title("Available");
 
     let selected_panel = Panel::new(
         UnitTree::new()
@@ -346,6 +820,16 @@ where
         move |s| add_random(s, state.clone())
     })
     .on_event(Event::Char('d'), remove_selected)
+    .on_event(Event::Char('v'), choose_variant)
+    .on_event(Event::Char('s'), choose_stores)
+    .on_event(Event::Char('u'), choose_settings)
+    .on_event(Event::Char('p'), choose_route)
+    .on_event(Event::Char('e'), edit_formation)
+    .on_event(Event::Char('l'), toggle_leader)
+    .on_event(Event::Char('i'), show_info)
+    .on_event(Event::Char('c'), add_class)
+    .on_event(Event::Char('g'), toggle_grouped)
+    .on_event(Event::Char('/'), search)
     .on_event(Event::Key(event::Key::F1), |s| {
         s.add_layer(keybinding_dialog())
     })
@@ -354,6 +838,15 @@ where
 const KEYBINDING_TEXT: &str = "d - Remove Unit\n\
      f - Create Formation\n\
      r - Random\n\
+     v - Change Variant\n\
+     s - Change Stores\n\
+     u - Change Unit Settings\n\
+     p - Change Formation Route\n\
+     e - Edit Formation\n\
+     l - Toggle Formation Leader\n\
+     i - Unit Info\n\
+     c - Random of Class\n\
+     g - Group by Class\n\
      / - Search";
 
 fn keybinding_dialog() -> impl View {
@@ -368,9 +861,23 @@ fn keybinding_dialog() -> impl View {
 
 fn random_unit_view<F>(state: &AppState, on_submit: F) -> impl View
 where
-    // FIXME: calls with &str, we want &Nation & &UnitType
-    F: Fn(&mut Cursive, Option<Nation>, Option<UnitType>, usize) + Send + Sync + 'static,
+    F: Fn(&mut Cursive, UnitQuery, bool, usize) + Send + Sync + 'static,
 {
+    fn text_field(name: &str) -> impl View {
+        EditView::new().with_name(name).fixed_width(20)
+    }
+
+    fn text_content(s: &mut Cursive, name: &str) -> String {
+        s.call_on_name(name, |view: &mut EditView| view.get_content().to_string())
+            .unwrap_or_else(|| panic!("missing {name} view"))
+    }
+
+    /// Split a comma or whitespace separated list, e.g. `usn_*, wp_*`
+    fn split_list(str: &str) -> impl Iterator<Item = &str> {
+        str.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+    }
+
     Dialog::around(
         ListView::new()
             .child(
@@ -389,6 +896,25 @@ where
                     .with_name("random_type")
                     .max_width(20),
             )
+            .child(
+                "Size",
+                DefaultSelectView::new("<ANY>")
+                    .popup()
+                    .with_all(SizeClass::all().into_iter())
+                    .with_name("random_size")
+                    .max_width(20),
+            )
+            .child("Name Contains", text_field("random_name"))
+            .child("ID Patterns", text_field("random_ids"))
+            .child("Exclude IDs", text_field("random_exclude"))
+            .child("Hull Classes", text_field("random_hulls"))
+            .child("Min Speed (kn)", text_field("random_speed"))
+            .child("Sensors", text_field("random_sensors"))
+            .child("Weapons", text_field("random_weapons"))
+            .child(
+                "Random Variant",
+                Checkbox::new().with_name("random_variant"),
+            )
             .child(
                 "Number",
                 EditView::new()
@@ -399,23 +925,75 @@ where
             ),
     )
     .button("Create", move |s| {
+        let mut query = UnitQuery::new();
         let nation = s
             .call_on_name("random_nation", |view: &mut DefaultSelectView<Nation>| {
                 view.selection()
             })
             .expect("missing random_nation view");
+        if let Some(nation) = nation {
+            query = query.nation(nation);
+        }
         let utype = s
             .call_on_name("random_type", |view: &mut DefaultSelectView<UnitType>| {
                 view.selection()
             })
             .expect("missing random_type view");
-        let count = s
-            .call_on_name("random_count", |view: &mut EditView| {
-                // TODO: input validation
-                view.get_content().parse().expect("parse of count failed")
+        if let Some(utype) = utype {
+            query = query.utype(utype);
+        }
+        let size = s
+            .call_on_name("random_size", |view: &mut DefaultSelectView<SizeClass>| {
+                view.selection()
             })
-            .expect("missing random_count view");
-        on_submit(s, nation, utype, count);
+            .expect("missing random_size view");
+        if let Some(size) = size {
+            query = query.size_class(size);
+        }
+
+        let name = text_content(s, "random_name");
+        if !name.trim().is_empty() {
+            query = query.name(name.trim());
+        }
+        for pattern in split_list(&text_content(s, "random_ids")) {
+            query = query.id_pattern(pattern);
+        }
+        for id in split_list(&text_content(s, "random_exclude")) {
+            query = query.exclude(id);
+        }
+        for hull in split_list(&text_content(s, "random_hulls")) {
+            query = query.hull_class(hull);
+        }
+        let speed = text_content(s, "random_speed");
+        match speed.trim() {
+            "" => {}
+            speed => match speed.parse() {
+                Ok(speed) => query = query.min_speed(speed),
+                Err(_) => {
+                    s.add_layer(Dialog::info(format!("invalid min speed `{speed}`")));
+                    return;
+                }
+            },
+        }
+        for sensor in split_list(&text_content(s, "random_sensors")) {
+            query = query.sensor(sensor);
+        }
+        for weapon in split_list(&text_content(s, "random_weapons")) {
+            query = query.weapon(weapon);
+        }
+
+        let random_variant = s
+            .call_on_name("random_variant", |view: &mut Checkbox| view.is_checked())
+            .expect("missing random_variant view");
+        let count = text_content(s, "random_count");
+        let count = match count.trim().parse() {
+            Ok(count) if count > 0 => count,
+            _ => {
+                s.add_layer(Dialog::info(format!("invalid number `{count}`")));
+                return;
+            }
+        };
+        on_submit(s, query, random_variant, count);
         s.pop_layer();
     })
     .button("Cancel", |s| {
@@ -423,6 +1001,480 @@ where
     })
 }
 
+/// A dialog for picking the variant of a selected unit.
+///
+/// Specific variants are only listed for known units, random units can only
+/// use the default or a random variant.
+fn variant_view<F>(unit: &UnitOption, on_submit: F) -> impl View
+where
+    F: Fn(&mut Cursive, VariantOption) + Send + Sync + 'static,
+{
+    let mut variants = vec![VariantOption::Default, VariantOption::Random];
+    if let Some(unit) = unit.unit() {
+        variants.extend(unit.variants.iter().cloned().map(VariantOption::Variant));
+    }
+    // e.g. the variant of an imported unit that isn't in the unit database
+    if !variants.contains(unit.variant()) {
+        variants.push(unit.variant().clone());
+    }
+
+    let mut view = SelectView::new();
+    view.add_all(variants.into_iter().map(|v| (v.to_string(), v)));
+    let current = view.iter().position(|(_, v)| v == unit.variant());
+    if let Some(idx) = current {
+        view.set_selection(idx);
+    }
+
+    Dialog::around(view.on_submit(move |s, variant: &VariantOption| {
+        on_submit(s, variant.clone());
+        s.pop_layer();
+    }))
+    .title("Variant")
+    .button("Cancel", |s| {
+        s.pop_layer();
+    })
+}
+
+/// A dialog for picking the stores of a selected unit.
+///
+/// Like `variant_view`, named loadouts are only listed for known units.
+fn stores_view<F>(unit: &UnitOption, on_submit: F) -> impl View
+where
+    F: Fn(&mut Cursive, StoresOption) + Send + Sync + 'static,
+{
+    let mut options = vec![
+        StoresOption::Full,
+        StoresOption::Depleted,
+        StoresOption::Random,
+    ];
+    if let Some(unit) = unit.unit() {
+        let loadouts = unit.metadata.loadouts.iter().cloned();
+        options.extend(loadouts.map(StoresOption::Loadout));
+    }
+    if !options.contains(unit.stores()) {
+        options.push(unit.stores().clone());
+    }
+
+    let mut view = SelectView::new();
+    view.add_all(options.into_iter().map(|o| (o.to_string(), o)));
+    let current = view.iter().position(|(_, o)| o == unit.stores());
+    if let Some(idx) = current {
+        view.set_selection(idx);
+    }
+
+    let has_loadouts = view
+        .iter()
+        .any(|(_, o)| matches!(o, StoresOption::Loadout(_)));
+    let mut layout =
+        LinearLayout::vertical().child(view.on_submit(move |s, stores: &StoresOption| {
+            on_submit(s, stores.clone());
+            s.pop_layer();
+        }));
+    if has_loadouts {
+        layout.add_child(TextView::new(
+            "Loadouts are unconfirmed, the game may ignore them",
+        ));
+    }
+
+    Dialog::around(layout)
+        .title("Stores")
+        .button("Cancel", |s| {
+            s.pop_layer();
+        })
+}
+
+/// A dialog for picking the route a formation follows.
+fn route_view<F>(route: RouteStyle, on_submit: F) -> impl View
+where
+    F: Fn(&mut Cursive, RouteStyle) + Send + Sync + 'static,
+{
+    let mut view = SelectView::new();
+    view.add_all(RouteStyle::all().into_iter().map(|r| (r.to_string(), r)));
+    let current = view.iter().position(|(_, r)| *r == route);
+    if let Some(idx) = current {
+        view.set_selection(idx);
+    }
+
+    Dialog::around(view.on_submit(move |s, route: &RouteStyle| {
+        on_submit(s, *route);
+        s.pop_layer();
+    }))
+    .title("Route")
+    .button("Cancel", |s| {
+        s.pop_layer();
+    })
+}
+
+/// A dialog for changing the name, shape, spacing and spawn positions of a
+/// formation.
+fn formation_layout_view<F>(layout: &FormationLayout, on_submit: F) -> impl View
+where
+    F: Fn(&mut Cursive, FormationLayout) + Send + Sync + 'static,
+{
+    /// A select of the known values, and the current one if it isn't, e.g.
+    /// from an imported mission.
+    fn select<T>(known: impl IntoIterator<Item = T>, current: &T) -> SelectView<T>
+    where
+        T: Clone + PartialEq + ToString + Send + Sync + 'static,
+    {
+        let mut values: Vec<T> = known.into_iter().collect();
+        if !values.contains(current) {
+            values.push(current.clone());
+        }
+        let mut view = SelectView::new().popup();
+        view.add_all(values.into_iter().map(|v| (v.to_string(), v)));
+        let current = view.iter().position(|(_, v)| v == current);
+        if let Some(idx) = current {
+            view.set_selection(idx);
+        }
+        view
+    }
+
+    fn selection<T>(s: &mut Cursive, name: &str) -> Option<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        s.call_on_name(name, |view: &mut SelectView<T>| {
+            view.selection().map(|v| (*v).clone())
+        })
+        .flatten()
+    }
+
+    let spawn_positions = select(SpawnPositions::all(), &layout.spawn_positions);
+
+    let form = ListView::new()
+        .child(
+            "Name",
+            EditView::new()
+                .content(layout.name.clone())
+                .with_name("formation_name")
+                .fixed_width(20),
+        )
+        // any shape the game supports can be entered, not just the known
+        // ones
+        .child(
+            "Shape (e.g. Circle)",
+            EditView::new()
+                .content(layout.shape.to_string())
+                .with_name("formation_shape")
+                .fixed_width(20),
+        )
+        .child(
+            "Spacing (nm)",
+            EditView::new()
+                .content(layout.spacing.to_string())
+                .with_name("formation_spacing")
+                .fixed_width(6),
+        )
+        .child(
+            "Spawn Positions",
+            spawn_positions.with_name("formation_spawn_positions"),
+        );
+
+    Dialog::around(form)
+        .title("Formation")
+        .button("Ok", move |s| {
+            let content = |s: &mut Cursive, name: &str| {
+                s.call_on_name(name, |view: &mut EditView| view.get_content())
+                    .unwrap()
+                    .trim()
+                    .to_owned()
+            };
+            // the rest is checked along with the other options
+            let name = content(s, "formation_name");
+            let shape = match content(s, "formation_shape").parse::<FormationShape>() {
+                Ok(shape) => shape,
+                Err(error) => {
+                    s.add_layer(Dialog::info(error.to_string()));
+                    return;
+                }
+            };
+            let spacing = content(s, "formation_spacing");
+            let Ok(spacing) = spacing.parse() else {
+                s.add_layer(Dialog::info(format!("invalid spacing `{spacing}`")));
+                return;
+            };
+            let spawn_positions =
+                selection(s, "formation_spawn_positions").unwrap_or(SpawnPositions::Override);
+            let layout = FormationLayout {
+                name,
+                shape,
+                spacing,
+                spawn_positions,
+            };
+            on_submit(s, layout);
+            s.pop_layer();
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        })
+}
+
+/// Change unit `settings`, where any left as `inherited` are inherited, e.g.
+/// from the taskforce. The mission type and station role may be labelled
+/// `automatic` instead, since without them each unit is given ones suited to
+/// it.
+fn unit_settings_view<F>(
+    settings: &UnitSettings,
+    inherited: &str,
+    automatic: &str,
+    on_submit: F,
+) -> impl View
+where
+    F: Fn(&mut Cursive, UnitSettings) + Send + Sync + 'static,
+{
+    fn select<T>(inherited: &str, values: impl Iterator<Item = T>, value: Option<T>) -> impl View
+    where
+        T: Clone + PartialEq + ToString + Send + Sync + 'static,
+    {
+        DefaultSelectView::new(inherited)
+            .with_all(values)
+            .selected(value.as_ref())
+            .popup()
+    }
+
+    fn selection<T>(s: &mut Cursive, name: &str) -> Option<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        s.call_on_name(name, |view: &mut DefaultSelectView<T>| view.selection())
+            .flatten()
+    }
+
+    let bools = || [true, false].into_iter();
+    let form = ListView::new()
+        .child(
+            "Mission",
+            select(
+                automatic,
+                MissionType::all().into_iter(),
+                settings.mission_type,
+            )
+            .with_name("settings_mission_type"),
+        )
+        .child(
+            "Station Role",
+            select(
+                automatic,
+                StationRole::all().into_iter(),
+                settings.station_role,
+            )
+            .with_name("settings_station_role"),
+        )
+        .child(
+            &format!("Telegraph (0-{})", mission::MAX_TELEGRAPH),
+            EditView::new()
+                .content(
+                    settings
+                        .telegraph
+                        .map(|t| t.to_string())
+                        .unwrap_or_default(),
+                )
+                .with_name("settings_telegraph")
+                .fixed_width(6),
+        )
+        .child(
+            "Crew Skill",
+            select(inherited, CrewSkill::all().into_iter(), settings.crew_skill)
+                .with_name("settings_crew_skill"),
+        )
+        .child(
+            "Radars Active",
+            select(inherited, bools(), settings.radars_active).with_name("settings_radars"),
+        )
+        .child(
+            "Active Sonars",
+            select(inherited, bools(), settings.active_sonars_enabled).with_name("settings_sonars"),
+        )
+        .child(
+            "Towed Array",
+            select(inherited, bools(), settings.towed_array_deployed)
+                .with_name("settings_towed_array"),
+        )
+        .child(
+            "Towed Decoy",
+            select(inherited, bools(), settings.towed_decoy_deployed)
+                .with_name("settings_towed_decoy"),
+        )
+        .child(
+            "Damage",
+            select(inherited, Damage::all().into_iter(), settings.damage)
+                .with_name("settings_damage"),
+        );
+
+    Dialog::around(form)
+        .title("Unit Settings")
+        .button("Ok", move |s| {
+            let telegraph = s
+                .call_on_name("settings_telegraph", |view: &mut EditView| {
+                    view.get_content()
+                })
+                .unwrap();
+            let telegraph = match telegraph.trim() {
+                "" => None,
+                telegraph => match telegraph.parse() {
+                    Ok(telegraph) if telegraph <= mission::MAX_TELEGRAPH => Some(telegraph),
+                    _ => {
+                        s.add_layer(Dialog::info(format!("invalid telegraph `{telegraph}`")));
+                        return;
+                    }
+                },
+            };
+            let settings = UnitSettings {
+                telegraph,
+                crew_skill: selection(s, "settings_crew_skill"),
+                radars_active: selection(s, "settings_radars"),
+                active_sonars_enabled: selection(s, "settings_sonars"),
+                towed_array_deployed: selection(s, "settings_towed_array"),
+                towed_decoy_deployed: selection(s, "settings_towed_decoy"),
+                damage: selection(s, "settings_damage"),
+                mission_type: selection(s, "settings_mission_type"),
+                station_role: selection(s, "settings_station_role"),
+            };
+            on_submit(s, settings);
+            s.pop_layer();
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        })
+}
+
+/// A button to change the settings every unit in a taskforce starts with.
+///
+/// The `fetcher` returns the taskforce given a mission, see `fill_taskforce`.
+fn taskforce_settings_button<F>(mission: Arc<Mutex<MissionOptions>>, fetcher: F) -> Button
+where
+    F: Fn(&mut MissionOptions) -> &mut TaskforceOptions + Copy + Send + Sync + 'static,
+{
+    Button::new("Customise...", move |s| {
+        let settings = fetcher(&mut mission.lock().unwrap()).settings.clone();
+        let mission = mission.clone();
+        let view = unit_settings_view(
+            &settings,
+            "<GAME DEFAULT>",
+            "<AUTOMATIC>",
+            move |_, settings| {
+                fetcher(&mut mission.lock().unwrap()).settings = settings;
+            },
+        );
+        s.add_layer(view);
+    })
+}
+
+/// A select for the route of the units in a taskforce that aren't in a
+/// formation.
+///
+/// The `fetcher` returns the taskforce given a mission, see `fill_taskforce`.
+fn taskforce_route_select<F>(
+    mission: Arc<Mutex<MissionOptions>>,
+    fetcher: F,
+) -> SelectView<RouteStyle>
+where
+    F: Fn(&mut MissionOptions) -> &mut TaskforceOptions + Copy + Send + Sync + 'static,
+{
+    let mut view = SelectView::new().popup();
+    view.add_all(RouteStyle::all().into_iter().map(|r| (r.to_string(), r)));
+    let current = fetcher(&mut mission.lock().unwrap()).route;
+    let idx = view.iter().position(|(_, r)| *r == current);
+    if let Some(idx) = idx {
+        view.set_selection(idx);
+    }
+    view.on_submit(move |_, route: &RouteStyle| {
+        fetcher(&mut mission.lock().unwrap()).route = *route;
+    })
+}
+
+/// An incremental search box, narrowing the available units as the user
+/// types.
+fn search_view(query: &str) -> impl View {
+    fn update(s: &mut Cursive, query: &str, _cursor: usize) {
+        s.call_on_name("available", |available: &mut UnitTable| {
+            available.search(query)
+        });
+        s.call_on_name("filter_search", |view: &mut TextView| {
+            view.set_content(query)
+        });
+    }
+
+    fn close(s: &mut Cursive) {
+        s.pop_layer();
+    }
+
+    OnEventView::new(
+        Dialog::around(
+            EditView::new()
+                .content(query)
+                .on_edit(update)
+                .on_submit(|s, _| close(s))
+                .fixed_width(30),
+        )
+        .title("Search")
+        .button("Clear", |s| {
+            update(s, "", 0);
+            close(s);
+        }),
+    )
+    .on_event(Event::Key(event::Key::Esc), close)
+}
+
+/// A dialog describing the performance and fit of a unit.
+fn unit_info_view(unit: &Unit) -> impl View {
+    fn or_unknown<T: ToString>(value: Option<T>) -> String {
+        value
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "Unknown".into())
+    }
+
+    fn or_none(values: &[String]) -> String {
+        if values.is_empty() {
+            "None".into()
+        } else {
+            values.join(", ")
+        }
+    }
+
+    let metadata = &unit.metadata;
+    let text = format!(
+        "Nation: {}\n\
+         Type: {}\n\
+         Source: {}\n\
+         Hull: {}\n\
+         Size: {}\n\
+         Displacement (t): {}\n\
+         Max Speed (kn): {}\n\
+         Crew: {}\n\
+         In Service: {}\n\
+         Hulls: {}\n\
+         Sensors: {}\n\
+         Weapons: {}\n\
+         Loadouts: {}",
+        unit.nation,
+        unit.utype,
+        unit.source,
+        or_unknown(metadata.hull_class.as_ref()),
+        or_unknown(metadata.size_class()),
+        or_unknown(metadata.displacement),
+        or_unknown(metadata.max_speed),
+        or_unknown(metadata.crew),
+        metadata.service,
+        or_none(&unit.hull_names),
+        or_none(&metadata.sensors),
+        or_none(&metadata.weapons),
+        or_none(&metadata.loadouts),
+    );
+
+    OnEventView::new(
+        Dialog::around(TextView::new(text))
+            .title(unit.name.clone())
+            .button("Close", |s| {
+                s.pop_layer();
+            }),
+    )
+    .on_event(Event::Key(event::Key::Esc), |s| {
+        s.pop_layer();
+    })
+}
+
 /// A `SelectView` of all nations, excluding civilian. It is intended to be
 /// be used for selecting a nation for red & blue, so civilian doesn't make
 /// sense.
@@ -439,27 +1491,146 @@ fn nation_select_view(nations: &[Nation]) -> SelectView {
     SelectView::new().popup().with_all_str(nations.iter())
 }
 
-// Fill mission options based off what is currently in the UI.
-fn fill_mission(s: &mut Cursive, mission: &mut MissionOptions) {
-    let lat = s
-        .call_on_name("latitude", |view: &mut EditView| view.get_content())
-        .unwrap();
-    let lon = s
-        .call_on_name("longitude", |view: &mut EditView| view.get_content())
-        .unwrap();
-    // fixme: unwrap
-    let latlon = (lat.parse().unwrap(), lon.parse().unwrap());
+// Fill mission options based off what is currently in the UI, returning
+// every field that is invalid.
+fn fill_mission(s: &mut Cursive, mission: &mut MissionOptions) -> Result<(), Vec<String>> {
+    fn content(s: &mut Cursive, name: &str) -> String {
+        s.call_on_name(name, |view: &mut EditView| view.get_content())
+            .unwrap()
+            .trim()
+            .to_owned()
+    }
+
+    fn parse<T: FromStr>(
+        s: &mut Cursive,
+        name: &str,
+        label: &str,
+        errors: &mut Vec<String>,
+    ) -> Option<T> {
+        let content = content(s, name);
+        let parsed = content.parse().ok();
+        if parsed.is_none() {
+            errors.push(format!("invalid {label} `{content}`"));
+        }
+        parsed
+    }
+
+    /// Parse a field that may be left blank, `Some(None)` if it is.
+    fn optional<T: FromStr>(
+        s: &mut Cursive,
+        name: &str,
+        label: &str,
+        errors: &mut Vec<String>,
+    ) -> Option<Option<T>> {
+        match content(s, name).as_str() {
+            "" => Some(None),
+            _ => parse(s, name, label, errors).map(Some),
+        }
+    }
+
+    let mut errors = Vec::new();
+    let lat = parse(s, "latitude", "latitude", &mut errors);
+    let lon = parse(s, "longitude", "longitude", &mut errors);
+    let width = parse(s, "size_w", "width", &mut errors);
+    let height = parse(s, "size_h", "height", &mut errors);
+    let date = parse(s, "date", "date", &mut errors);
+
+    // the land area is optional, but land units can't be placed without it
+    let land_x = optional(s, "land_x", "land area x", &mut errors);
+    let land_y = optional(s, "land_y", "land area y", &mut errors);
+    let land_w = optional(s, "land_w", "land area width", &mut errors);
+    let land_h = optional(s, "land_h", "land area height", &mut errors);
+    let land = match (land_x, land_y, land_w, land_h) {
+        (Some(Some(x)), Some(Some(y)), Some(Some(w)), Some(Some(h))) => {
+            Some(Some(mission::LandArea {
+                offset: (x, y),
+                size: (w, h),
+            }))
+        }
+        (Some(None), Some(None), Some(None), Some(None)) => Some(None),
+        // any invalid fields have already been reported
+        (Some(_), Some(_), Some(_), Some(_)) => {
+            errors.push("incomplete land area, fill in all of its fields or none".into());
+            None
+        }
+        _ => None,
+    };
+
+    // blank separation fields aren't enforced
+    let min_spacing = optional(s, "min_spacing", "min spacing", &mut errors);
+    let min_enemy_range = optional(s, "min_enemy_range", "min enemy range", &mut errors);
+    let max_enemy_range = optional(s, "max_enemy_range", "max enemy range", &mut errors);
+    let mut placement = |name: &str| {
+        s.call_on_name(name, |view: &mut SelectView<mission::PlacementStrategy>| {
+            view.selection().map(|p| *p)
+        })
+        .flatten()
+        .unwrap_or_default()
+    };
+    let neutral_placement = placement("neutral_placement");
+    let blue_placement = placement("blue_placement");
+    let red_placement = placement("red_placement");
+
+    // always pick the seed here, so that it can be shown to the user
+    let seed = match content(s, "seed").as_str() {
+        "" => Some(rand_ext::random_seed()),
+        _ => parse(s, "seed", "seed", &mut errors),
+    };
 
-    let width = s
-        .call_on_name("size_w", |view: &mut EditView| view.get_content())
+    // blank environment fields are random
+    let time = optional(s, "time", "time", &mut errors);
+    let sea_state = optional(s, "sea_state", "sea state", &mut errors);
+    let random_day = s
+        .call_on_name("random_day", |view: &mut Checkbox| view.is_checked())
         .unwrap();
-    let height = s
-        .call_on_name("size_h", |view: &mut EditView| view.get_content())
+    let clouds = s
+        .call_on_name("clouds", |view: &mut DefaultSelectView<mission::Clouds>| {
+            view.selection()
+        })
+        .unwrap();
+    let wind_direction = s
+        .call_on_name(
+            "wind_direction",
+            |view: &mut DefaultSelectView<mission::WindDirection>| view.selection(),
+        )
         .unwrap();
-    // fixme: unwrap
-    let size = (width.parse().unwrap(), height.parse().unwrap());
 
-    mission.general = mission::GeneralOptions { latlon, size };
+    let (Some(lat), Some(lon), Some(width), Some(height), Some(date), Some(seed)) =
+        (lat, lon, width, height, date, seed)
+    else {
+        return Err(errors);
+    };
+    let (Some(time), Some(sea_state), Some(land)) = (time, sea_state, land) else {
+        return Err(errors);
+    };
+    let (Some(min_spacing), Some(min_enemy_range), Some(max_enemy_range)) =
+        (min_spacing, min_enemy_range, max_enemy_range)
+    else {
+        return Err(errors);
+    };
+    mission.environment = mission::EnvironmentOptions {
+        random_day,
+        time,
+        sea_state,
+        clouds,
+        wind_direction,
+    };
+    mission.seed = Some(seed);
+    mission.general = mission::GeneralOptions {
+        latlon: (lat, lon),
+        size: (width, height),
+        date,
+        land,
+        separation: mission::SeparationOptions {
+            min_spacing,
+            min_enemy_range,
+            max_enemy_range,
+        },
+    };
+    mission.neutral.placement = neutral_placement;
+    mission.blue.placement = blue_placement;
+    mission.red.placement = red_placement;
+    Ok(())
 }
 
 /// Fill taskforce options based off what was selected by in the UI.
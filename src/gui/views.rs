@@ -26,6 +26,22 @@ impl<T: Clone + Send + Sync + 'static> DefaultSelectView<T> {
         self
     }
 
+    /// Select `value` if it is one of the items, otherwise the sentinel
+    /// stays selected.
+    pub fn selected(mut self, value: Option<&T>) -> Self
+    where
+        T: PartialEq,
+    {
+        let idx = self
+            .view
+            .iter()
+            .position(|(_, item)| item.as_ref() == value);
+        if let Some(idx) = idx {
+            self.view.set_selection(idx);
+        }
+        self
+    }
+
     pub fn popup(mut self) -> Self {
         self.view.set_popup(true);
         self
@@ -61,5 +77,10 @@ mod tests {
         assert_eq!(view.selection(), None); // defaults to none
         view.view.set_selection(1);
         assert_eq!(view.selection(), Some(1));
+
+        let view = DefaultSelectView::new("<EMPTY>")
+            .with_all(vec![1, 2, 3].into_iter())
+            .selected(Some(&3));
+        assert_eq!(view.selection(), Some(3));
     }
 }
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::unit_db as db;
 
 use cursive::align::HAlign;
@@ -7,44 +10,111 @@ use cursive_table_view::{TableView, TableViewItem};
 
 /// A table view that keeps track of all available units.
 pub struct UnitTable {
-    all_units: Vec<db::Unit>,
+    unit_db: Arc<db::UnitDb>,
+    /// the current filters, only matching units are shown
+    nation: Option<String>,
+    utype: Option<String>,
+    /// only show units in service during this year
+    year: Option<u16>,
+    query: String,
+    /// show a single row per class, instead of every unit
+    grouped: bool,
     view: TableView<db::Unit, UnitColumn>,
 }
 
 impl UnitTable {
-    /// Create a new unit table with a list of all available units.
-    pub fn new(all_units: Vec<db::Unit>) -> Self {
+    /// Create a new unit table showing every unit of `unit_db`.
+    pub fn new(unit_db: Arc<db::UnitDb>) -> Self {
         let view = TableView::<db::Unit, UnitColumn>::new()
             .column(UnitColumn::Name, "Name", |c| c.align(HAlign::Left))
             .column(UnitColumn::Nation, "Nation", |c| {
                 c.align(HAlign::Center).width_percent(20)
             })
             .column(UnitColumn::Type, "Type", |c| {
-                c.align(HAlign::Right).width_percent(20)
+                c.align(HAlign::Center).width_percent(15)
+            })
+            .column(UnitColumn::Source, "Source", |c| {
+                c.align(HAlign::Right).width_percent(15)
             })
-            .items(all_units.clone());
-        Self { all_units, view }
+            .items(unit_db.fuzzy_search("").into_iter().cloned().collect());
+        Self {
+            unit_db,
+            nation: None,
+            utype: None,
+            year: None,
+            query: String::new(),
+            grouped: false,
+            view,
+        }
     }
 
-    /// Filter units by nation or type.
+    /// Filter units by nation or type, keeping the current search.
     pub fn filter(&mut self, nation: Option<&str>, utype: Option<&str>) {
-        self.view.set_items(
-            self.all_units
-                .iter()
-                .filter(|unit| nation.map(|n| n == unit.nation.to_string()).unwrap_or(true))
-                .filter(|unit| utype.map(|t| t == unit.utype.to_string()).unwrap_or(true))
-                .cloned()
-                .collect(),
-        );
+        self.nation = nation.map(str::to_owned);
+        self.utype = utype.map(str::to_owned);
+        self.refresh();
+    }
+
+    /// Only show units in service during `year`, keeping the other filters.
+    pub fn in_service(&mut self, year: Option<u16>) {
+        self.year = year;
+        self.refresh();
+    }
+
+    /// Fuzzy search units, keeping the current filters. The best matches are
+    /// shown first, unless the table has been sorted by a column.
+    pub fn search(&mut self, query: &str) {
+        self.query = query.to_owned();
+        self.refresh();
     }
 
-    // pub fn search(&mut self, name: &str) { }
+    /// Toggle between showing every unit and a single row per class, where
+    /// the row is the best match of the class.
+    pub fn toggle_grouped(&mut self) {
+        self.grouped = !self.grouped;
+        self.refresh();
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.grouped
+    }
+
+    fn refresh(&mut self) {
+        let nation = self.nation.as_deref();
+        let utype = self.utype.as_deref();
+        let items = self
+            .unit_db
+            .fuzzy_search(&self.query)
+            .into_iter()
+            .filter(|unit| nation.map(|n| n == unit.nation.to_string()).unwrap_or(true))
+            .filter(|unit| utype.map(|t| t == unit.utype.to_string()).unwrap_or(true))
+            .filter(|unit| {
+                self.year
+                    .map(|y| unit.metadata.service.contains(y))
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+        let items = if self.grouped {
+            group_by_class(items)
+        } else {
+            items
+        };
+        self.view.set_items(items);
+    }
 
     /// Return an immutable reference to the unit at the given row.
     pub fn borrow_item(&self, row: usize) -> Option<&db::Unit> {
         self.view.borrow_item(row)
     }
 
+    /// Return an immutable reference to the currently focused unit.
+    pub fn selected_item(&self) -> Option<&db::Unit> {
+        self.view
+            .item()
+            .and_then(|index| self.view.borrow_item(index))
+    }
+
     /// Callback for when a unit has been "submitted", i.e. has been selected
     /// for addition into another view.
     pub fn on_submit<F>(mut self, cb: F) -> Self
@@ -56,6 +126,30 @@ impl UnitTable {
     }
 }
 
+/// Collapse `units` into the first unit of each class, keeping their order.
+/// Classes with more than one unit have the number of units appended to
+/// their name, e.g. `Kashin (2)`.
+fn group_by_class(units: Vec<db::Unit>) -> Vec<db::Unit> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for unit in &units {
+        *counts.entry(unit.class_id.clone()).or_default() += 1;
+    }
+
+    let mut grouped = Vec::new();
+    for mut unit in units {
+        match counts.remove(&unit.class_id) {
+            Some(count) if count > 1 => {
+                unit.name = format!("{} ({count})", unit.name);
+                grouped.push(unit);
+            }
+            Some(_) => grouped.push(unit),
+            // already grouped
+            None => continue,
+        }
+    }
+    grouped
+}
+
 impl ViewWrapper for UnitTable {
     wrap_impl!(self.view: TableView<db::Unit, UnitColumn>);
 }
@@ -65,6 +159,7 @@ pub enum UnitColumn {
     Name,
     Nation,
     Type,
+    Source,
 }
 
 impl TableViewItem<UnitColumn> for db::Unit {
@@ -73,6 +168,7 @@ impl TableViewItem<UnitColumn> for db::Unit {
             UnitColumn::Name => self.name.clone(),
             UnitColumn::Nation => self.nation.to_string(),
             UnitColumn::Type => self.utype.to_string(),
+            UnitColumn::Source => self.source.to_string(),
         }
     }
 
@@ -81,6 +177,7 @@ impl TableViewItem<UnitColumn> for db::Unit {
             UnitColumn::Name => self.name.cmp(&other.name),
             UnitColumn::Nation => self.nation.name.cmp(&other.nation.name),
             UnitColumn::Type => self.utype.to_string().cmp(&other.utype.to_string()),
+            UnitColumn::Source => self.source.cmp(&other.source),
         }
     }
 }
@@ -96,11 +193,17 @@ mod tests {
         db::Unit {
             id: Faker.fake(),
             name: Faker.fake(),
+            localised_names: Default::default(),
+            hull_names: Vec::new(),
+            class_id: Faker.fake(),
             nation: db::Nation {
                 id: Faker.fake(),
                 name: Faker.fake(),
             },
             utype: *db::UnitType::all().choose(&mut thread_rng()).unwrap(),
+            variants: Vec::new(),
+            metadata: Default::default(),
+            source: db::UnitSource::Original,
         }
     }
 
@@ -110,6 +213,7 @@ mod tests {
         assert_eq!(unit.to_column(UnitColumn::Name), unit.name);
         assert_eq!(unit.to_column(UnitColumn::Nation), unit.nation.to_string());
         assert_eq!(unit.to_column(UnitColumn::Type), unit.utype.to_string());
+        assert_eq!(unit.to_column(UnitColumn::Source), "Original");
     }
 
     // TODO: consider using quickcheck to make these tests more extensive
@@ -155,6 +259,30 @@ mod tests {
         assert_eq!(unit2.cmp(&unit1, UnitColumn::Type), Ordering::Greater);
     }
 
+    #[test]
+    fn db_unit_cmp_source() {
+        let mut unit1 = fake_unit();
+        let mut unit2 = fake_unit();
+        assert_eq!(unit1.cmp(&unit2, UnitColumn::Source), Ordering::Equal);
+
+        // overrides sort after the original data
+        unit2.source = db::UnitSource::Mod("a".into());
+        assert_eq!(unit1.cmp(&unit2, UnitColumn::Source), Ordering::Less);
+        unit1.source = db::UnitSource::User;
+        assert_eq!(unit1.cmp(&unit2, UnitColumn::Source), Ordering::Greater);
+    }
+
+    /// `count` fake units, ordered by id like the table shows them.
+    fn fake_units(count: usize) -> Vec<db::Unit> {
+        let mut units: Vec<db::Unit> = (0..count).map(|_| fake_unit()).collect();
+        units.sort_by(|a, b| a.id.cmp(&b.id));
+        units
+    }
+
+    fn table(units: &[db::Unit]) -> UnitTable {
+        UnitTable::new(Arc::new(db::UnitDb::from_units(units.to_vec())))
+    }
+
     fn assert_eq_table(table: &UnitTable, row: usize, unit: &db::Unit) {
         assert_eq!(
             table.borrow_item(row).map(|u| u.id.clone()),
@@ -164,9 +292,9 @@ mod tests {
 
     #[test]
     fn unit_table_borrow_item() {
-        let units: Vec<db::Unit> = (0..3).map(|_| fake_unit()).collect();
-        let table = UnitTable::new(units.clone());
-        // order should be preserved
+        let units = fake_units(3);
+        let table = table(&units);
+        // ordered by id
         for (row, unit) in units.iter().enumerate() {
             assert_eq_table(&table, row, unit);
         }
@@ -176,8 +304,8 @@ mod tests {
 
     #[test]
     fn unit_table_filter() {
-        let units: Vec<db::Unit> = (0..3).map(|_| fake_unit()).collect();
-        let mut table = UnitTable::new(units.clone());
+        let units = fake_units(3);
+        let mut table = table(&units);
 
         let nation = &units[0].nation.to_string();
         let utype = &units[0].utype.to_string();
@@ -188,30 +316,98 @@ mod tests {
 
         // only one nation
         table.filter(Some(nation), None);
-        for (row, unit) in units.iter().enumerate() {
-            if unit.nation.to_string() == *nation {
-                assert_eq_table(&table, row, &unit);
-            }
+        let expected = units.iter().filter(|u| u.nation.to_string() == *nation);
+        for (row, unit) in expected.enumerate() {
+            assert_eq_table(&table, row, unit);
         }
 
         // only one type
         table.filter(None, Some(utype));
-        for (row, unit) in units.iter().enumerate() {
-            if unit.utype.to_string() == *utype {
-                assert_eq_table(&table, row, &unit);
-            }
+        let expected = units.iter().filter(|u| u.utype.to_string() == *utype);
+        for (row, unit) in expected.enumerate() {
+            assert_eq_table(&table, row, unit);
         }
 
         // both nation and type
         table.filter(Some(nation), Some(utype));
-        for (row, unit) in units.iter().enumerate() {
-            if unit.nation.to_string() == *nation && unit.utype.to_string() == *utype {
-                assert_eq_table(&table, row, &unit);
-            }
+        let expected = units
+            .iter()
+            .filter(|u| u.nation.to_string() == *nation && u.utype.to_string() == *utype);
+        for (row, unit) in expected.enumerate() {
+            assert_eq_table(&table, row, unit);
         }
 
         // neither
         table.filter(Some("MISSING"), Some("MISSING"));
         assert!(table.borrow_item(0).is_none());
     }
+
+    #[test]
+    fn unit_table_search() {
+        let mut units = fake_units(3);
+        units[0].name = "Kashin".into();
+        units[1].name = "Kashin Mod".into();
+        units[2].name = "Kidd".into();
+        // avoid accidental fuzzy matches on random data
+        for (idx, unit) in units.iter_mut().enumerate() {
+            unit.id = format!("id{idx}");
+            unit.nation.id = "n".into();
+            unit.nation.name = "Nation".into();
+        }
+        let mut table = table(&units);
+
+        table.search("kashin");
+        assert_eq_table(&table, 0, &units[0]);
+        assert_eq_table(&table, 1, &units[1]);
+
+        // search is combined with filters
+        table.filter(Some("MISSING"), None);
+        assert!(table.borrow_item(0).is_none());
+        table.filter(None, None);
+        assert_eq_table(&table, 0, &units[0]);
+
+        table.search("");
+        assert!(table.borrow_item(units.len() - 1).is_some());
+    }
+
+    #[test]
+    fn unit_table_in_service() {
+        let mut units = fake_units(2);
+        units[0].metadata.service.introduced = Some(1975);
+        units[1].metadata.service.retired = Some(1970);
+        let mut table = table(&units);
+
+        table.in_service(Some(1985));
+        assert_eq_table(&table, 0, &units[0]);
+        assert!(table.borrow_item(1).is_none());
+
+        table.in_service(Some(1965));
+        assert_eq_table(&table, 0, &units[1]);
+        assert!(table.borrow_item(1).is_none());
+
+        table.in_service(None);
+        assert!(table.borrow_item(1).is_some());
+    }
+
+    #[test]
+    fn unit_table_grouped() {
+        let mut units = fake_units(3);
+        units[0].name = "Kashin".into();
+        units[0].class_id = "wp_kashin".into();
+        units[1].class_id = "wp_kresta".into();
+        units[2].class_id = "wp_kashin".into();
+        let mut table = table(&units);
+
+        table.toggle_grouped();
+        assert!(table.is_grouped());
+        assert_eq!(
+            table.borrow_item(0).map(|u| u.name.as_str()),
+            Some("Kashin (2)")
+        );
+        assert_eq_table(&table, 1, &units[1]);
+        assert!(table.borrow_item(2).is_none());
+
+        table.toggle_grouped();
+        assert_eq_table(&table, 2, &units[2]);
+    }
 }
@@ -1,4 +1,7 @@
-use crate::mission::{FormationOption, TaskforceOptions, UnitOption};
+use crate::mission::{
+    FormationLayout, FormationOption, RouteStyle, StoresOption, TaskforceOptions, UnitOption,
+    UnitSettings, VariantOption,
+};
 
 use crate::gui::reusable_id::ReusableId;
 use cursive::view::ViewWrapper;
@@ -13,26 +16,52 @@ pub struct UnitSelection {
     // to increase or decrease the number vessels.
     unit: UnitOption,
     count: usize,
+    /// whether the unit leads its formation
+    leader: bool,
 }
 
 impl UnitSelection {
     fn new(unit: UnitOption) -> Self {
-        Self { unit, count: 1 }
+        Self {
+            unit,
+            count: 1,
+            leader: false,
+        }
     }
 
     fn name(&self) -> String {
         let unit_str = match &self.unit {
-            UnitOption::Unit(unit) => unit.name.clone(),
-            UnitOption::Random { nation, utype } => {
-                // TODO: cleanup, will want to add more filters later
-                match (nation, utype) {
-                    (Some(nation), Some(utype)) => format!("<RANDOM {nation} {utype}>"),
-                    (Some(nation), None) => format!("<RANDOM {nation}>"),
-                    (None, Some(utype)) => format!("<RANDOM {utype}>"),
-                    (None, None) => "<RANDOM>".into(),
+            UnitOption::Unit { unit, .. } => unit.name.clone(),
+            UnitOption::Imported { unit, .. } => match &unit.unit {
+                Some(known) => known.name.clone(),
+                None => unit.id.clone(),
+            },
+            UnitOption::Random { query, .. } => {
+                let query = query.to_string();
+                if query.is_empty() {
+                    "<RANDOM>".into()
+                } else {
+                    format!("<RANDOM {query}>")
                 }
             }
         };
+        let unit_str = match self.unit.variant() {
+            VariantOption::Default => unit_str,
+            variant => format!("{unit_str} ({variant})"),
+        };
+        let unit_str = match self.unit.stores() {
+            StoresOption::Full => unit_str,
+            stores => format!("{unit_str} [{stores}]"),
+        };
+        // the settings don't fit, just show that there are some
+        let unit_str = match self.unit.settings().is_empty() {
+            true => unit_str,
+            false => format!("{unit_str} *"),
+        };
+        let unit_str = match self.leader {
+            true => format!("{unit_str} - Leader"),
+            false => unit_str,
+        };
 
         if self.count > 1 {
             format!("{unit_str} x {}", self.count)
@@ -50,10 +79,32 @@ impl UnitSelection {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct FormationItem {
+    id: usize,
+    route: RouteStyle,
+    layout: FormationLayout,
+}
+
+impl std::fmt::Display for FormationItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let layout = &self.layout;
+        write!(
+            f,
+            "Formation {} - {} ({}, {}nm)",
+            self.id, layout.name, layout.shape, layout.spacing
+        )?;
+        match self.route {
+            RouteStyle::None => Ok(()),
+            route => write!(f, " [{route}]"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum UnitTreeItem {
     Unit(UnitSelection),
-    Formation(usize),
+    Formation(FormationItem),
 }
 
 impl std::fmt::Display for UnitTreeItem {
@@ -61,17 +112,35 @@ impl std::fmt::Display for UnitTreeItem {
         use UnitTreeItem::*;
         match self {
             Unit(unit) => write!(f, "{}", unit.name()),
-            Formation(id) => write!(f, "Formation {id}"),
+            Formation(formation) => write!(f, "{formation}"),
         }
     }
 }
 
+/// The units of a formation, how they're arranged and how it moves.
+#[derive(Debug)]
+pub struct FormationSelection {
+    pub units: Vec<UnitSelection>,
+    pub route: RouteStyle,
+    pub layout: FormationLayout,
+}
+
+impl FormationSelection {
+    /// The index of the leader in the formation options, see
+    /// `FormationOption::leader`.
+    fn leader(&self) -> Option<usize> {
+        let idx = self.units.iter().position(|unit| unit.leader)?;
+        // units before the leader may be added many times
+        Some(self.units[..idx].iter().map(|unit| unit.count).sum())
+    }
+}
+
 /// All selected items from the `UnitTree`.
 // TODO: consider removing
 #[derive(Debug)]
 pub struct UnitTreeSelection {
     pub units: Vec<UnitSelection>,
-    pub formations: Vec<Vec<UnitSelection>>,
+    pub formations: Vec<FormationSelection>,
 }
 
 impl UnitTreeSelection {
@@ -89,7 +158,10 @@ impl UnitTreeSelection {
         self.formations
             .iter()
             .map(|f| FormationOption {
-                units: units_to_options(f),
+                units: units_to_options(&f.units),
+                layout: f.layout.clone(),
+                leader: f.leader(),
+                route: f.route,
             })
             .collect()
     }
@@ -101,7 +173,17 @@ impl From<&TaskforceOptions> for UnitTreeSelection {
         let formations = taskforce
             .formations
             .iter()
-            .map(|f| options_to_units(&f.units))
+            .map(|f| {
+                let mut units = options_to_units(&f.units);
+                if let Some(leader) = f.leader.and_then(|idx| units.get_mut(idx)) {
+                    leader.leader = true;
+                }
+                FormationSelection {
+                    units,
+                    route: f.route,
+                    layout: f.layout.clone(),
+                }
+            })
             .collect();
         Self { units, formations }
     }
@@ -137,9 +219,13 @@ impl UnitTree {
 
         for formation in selection.formations {
             self.add_formation();
+            if let Some(row) = self.row() {
+                self.set_route(row, formation.route);
+                self.set_layout(row, formation.layout);
+            }
             // FIXME: can use recursion for this
-            for unit in formation {
-                self.add_unit(unit.unit);
+            for unit in formation.units {
+                self.add_unit_selection(unit);
             }
         }
 
@@ -177,7 +263,10 @@ impl UnitTree {
     }
 
     pub fn add_n_units(&mut self, unit: UnitOption, count: usize) {
-        self.add_unit_selection(UnitSelection { unit, count })
+        self.add_unit_selection(UnitSelection {
+            count,
+            ..UnitSelection::new(unit)
+        })
     }
 
     /// Add a formation to the tree, any units added after this will be added
@@ -192,7 +281,11 @@ impl UnitTree {
         let n = self
             .view
             .insert_item(
-                UnitTreeItem::Formation(formation_id),
+                UnitTreeItem::Formation(FormationItem {
+                    id: formation_id,
+                    route: RouteStyle::None,
+                    layout: FormationLayout::default(),
+                }),
                 Placement::After,
                 insert_at,
             )
@@ -202,8 +295,8 @@ impl UnitTree {
 
     /// Remove an item from the list.
     pub fn remove(&mut self, row: usize) {
-        if let Some(UnitTreeItem::Formation(id)) = self.view.borrow_item(row) {
-            self.formation_id.release(*id);
+        if let Some(UnitTreeItem::Formation(formation)) = self.view.borrow_item(row) {
+            self.formation_id.release(formation.id);
         }
 
         // FIXME: there's a bug in cursive_tree_view that if you attempt
@@ -223,23 +316,114 @@ impl UnitTree {
         self.view.row()
     }
 
+    /// Return the unit at the given row, `None` if the row is a formation.
+    pub fn borrow_unit(&self, row: usize) -> Option<&UnitOption> {
+        match self.view.borrow_item(row) {
+            Some(UnitTreeItem::Unit(selection)) => Some(&selection.unit),
+            _ => None,
+        }
+    }
+
+    /// Change the variant of the unit at the given row, does nothing if the
+    /// row is a formation.
+    pub fn set_variant(&mut self, row: usize, variant: VariantOption) {
+        if let Some(UnitTreeItem::Unit(selection)) = self.view.borrow_item_mut(row) {
+            selection.unit.set_variant(variant);
+        }
+    }
+
+    /// Change the stores of the unit at the given row, does nothing if the
+    /// row is a formation.
+    pub fn set_stores(&mut self, row: usize, stores: StoresOption) {
+        if let Some(UnitTreeItem::Unit(selection)) = self.view.borrow_item_mut(row) {
+            selection.unit.set_stores(stores);
+        }
+    }
+
+    /// Change the settings of the unit at the given row, does nothing if the
+    /// row is a formation.
+    pub fn set_settings(&mut self, row: usize, settings: UnitSettings) {
+        if let Some(UnitTreeItem::Unit(selection)) = self.view.borrow_item_mut(row) {
+            selection.unit.set_settings(settings);
+        }
+    }
+
+    /// Make the unit at the given row lead its formation, or stop leading it
+    /// if it already does. Does nothing if the unit isn't in a formation.
+    pub fn toggle_leader(&mut self, row: usize) {
+        let Some(parent) = self.view.item_parent(row) else {
+            return;
+        };
+        let leader = match self.view.borrow_item(row) {
+            Some(UnitTreeItem::Unit(selection)) => !selection.leader,
+            _ => return,
+        };
+        // a formation has a single leader
+        for sibling in 0..self.view.len() {
+            if self.view.item_parent(sibling) != Some(parent) {
+                continue;
+            }
+            if let Some(UnitTreeItem::Unit(selection)) = self.view.borrow_item_mut(sibling) {
+                selection.leader = leader && sibling == row;
+            }
+        }
+    }
+
+    /// Return the route of the formation at the given row, `None` if the row
+    /// is a unit.
+    pub fn formation_route(&self, row: usize) -> Option<RouteStyle> {
+        match self.view.borrow_item(row) {
+            Some(UnitTreeItem::Formation(formation)) => Some(formation.route),
+            _ => None,
+        }
+    }
+
+    /// Change the route of the formation at the given row, does nothing if
+    /// the row is a unit.
+    pub fn set_route(&mut self, row: usize, route: RouteStyle) {
+        if let Some(UnitTreeItem::Formation(formation)) = self.view.borrow_item_mut(row) {
+            formation.route = route;
+        }
+    }
+
+    /// Return the layout of the formation at the given row, `None` if the
+    /// row is a unit.
+    pub fn formation_layout(&self, row: usize) -> Option<&FormationLayout> {
+        match self.view.borrow_item(row) {
+            Some(UnitTreeItem::Formation(formation)) => Some(&formation.layout),
+            _ => None,
+        }
+    }
+
+    /// Change the layout of the formation at the given row, does nothing if
+    /// the row is a unit.
+    pub fn set_layout(&mut self, row: usize, layout: FormationLayout) {
+        if let Some(UnitTreeItem::Formation(formation)) = self.view.borrow_item_mut(row) {
+            formation.layout = layout;
+        }
+    }
+
     /// Return all selected items (units & formations) from the tree.
     pub fn selected(&self) -> UnitTreeSelection {
         let mut units = Vec::new();
-        let mut formations: Vec<Vec<UnitSelection>> = Vec::new();
+        let mut formations: Vec<FormationSelection> = Vec::new();
         for item in self.items() {
             match item {
                 UnitTreeItem::Unit(unit) => {
                     // if we had previously added a formation then all subsequent
                     // units will be part of that formation.
                     if let Some(formation) = formations.last_mut() {
-                        formation.push(unit.clone());
+                        formation.units.push(unit.clone());
                     } else {
                         units.push(unit.clone());
                     }
                 }
-                UnitTreeItem::Formation(_) => {
-                    formations.push(Vec::new());
+                UnitTreeItem::Formation(formation) => {
+                    formations.push(FormationSelection {
+                        units: Vec::new(),
+                        route: formation.route,
+                        layout: formation.layout.clone(),
+                    });
                 }
             }
         }
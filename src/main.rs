@@ -3,17 +3,19 @@ mod dir;
 mod gui;
 mod mission;
 mod rand_ext;
+mod terrain;
 mod unit_db;
 
 use config::Config;
 use configparser::ini::Ini;
 use gui::AskForGamePathCommand;
-use mission::Mission;
+use mission::{Mission, MissionOptions};
 use std::error::Error;
 use std::path::PathBuf;
 use std::str;
 use std::sync::Arc;
-use unit_db::UnitDb;
+use terrain::Terrain;
+use unit_db::{EraOverrides, LoadReport, UnitDb};
 
 const MISSION_TEMPLATE: &str = include_str!("../resources/mission_template.ini");
 
@@ -27,6 +29,48 @@ fn config_file() -> PathBuf {
     dir::config_dir().join("config.ini")
 }
 
+fn unit_cache_file() -> PathBuf {
+    dir::config_dir().join("unit_cache.ini")
+}
+
+fn era_overrides_file() -> PathBuf {
+    dir::config_dir().join("era_overrides.ini")
+}
+
+fn terrain_file() -> PathBuf {
+    dir::config_dir().join("elevation.asc")
+}
+
+/// Load the elevation grid ships are kept off land with, if there is one.
+fn load_terrain() -> Terrain {
+    Terrain::load(&terrain_file())
+        // not fatal, ships may just end up on land
+        .inspect_err(|e| eprintln!("failed to load elevation grid: {}", e))
+        .unwrap_or_default()
+}
+
+/// Load the unit database, re-using anything cached by a previous launch.
+fn load_unit_db(config: &Config) -> (UnitDb, LoadReport) {
+    let cache_file = unit_cache_file();
+    let mut cache = unit_db::Cache::load(&cache_file, &config.game_root);
+    let (mut unit_db, report) =
+        UnitDb::new(&config.game_root, &mut cache).expect("failed to initialise UnitDB");
+
+    eprintln!("read {} unit files not found in cache", cache.misses());
+    if let Err(e) = cache.save(&cache_file) {
+        // not fatal, we just won't be any faster next time
+        eprintln!("failed to save unit cache: {}", e);
+    }
+    eprintln!("skipped {} unit files", report.skipped.len());
+
+    match EraOverrides::load(&era_overrides_file()) {
+        Ok(overrides) => unit_db.apply_era_overrides(&overrides),
+        // not fatal, the game data is used instead
+        Err(e) => eprintln!("failed to load era overrides: {}", e),
+    }
+    (unit_db, report)
+}
+
 fn load_config() -> Option<Config> {
     let config_file = config_file();
     eprintln!("attempting to load config from {}", config_file.display());
@@ -76,11 +120,30 @@ fn main() -> Result<(), Box<dyn Error>> {
         config.save(config_file())?;
     }
 
-    let unit_db = Arc::new(UnitDb::new(&config.game_root).expect("failed to initialise UnitDB"));
-    gui::App::new(&unit_db).run({
+    let (unit_db, report) = load_unit_db(&config);
+    let unit_db = Arc::new(unit_db);
+    let language = if unit_db.languages().contains(&config.language) {
+        config.language.as_str()
+    } else {
+        eprintln!(
+            "language `{}` is not installed, falling back to `{}`",
+            config.language,
+            unit_db::DEFAULT_LANGUAGE
+        );
+        unit_db::DEFAULT_LANGUAGE
+    };
+    let terrain = Arc::new(load_terrain());
+    let mut app = gui::App::new(&unit_db, language, report).with_terrain(terrain.clone());
+    // start from an existing mission if given one
+    if let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) {
+        let (options, report) = MissionOptions::load(&unit_db, &path)?;
+        eprintln!("imported {}", path.display());
+        app = app.with_import(options, report);
+    }
+    app.run({
         let unit_db = unit_db.clone();
         move |options| {
-            let mission = Mission::new(&unit_db.clone(), options);
+            let mission = Mission::new(&unit_db.clone(), &terrain, options);
 
             // FIXME: give for useful errors to user
             let mut mission_config = load_template().expect("load template failed");
@@ -90,6 +153,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             mission_config
                 .write(mission_path)
                 .expect("config write failed");
+            mission.problems().to_vec()
         }
     });
 
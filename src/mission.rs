@@ -1,10 +1,33 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-use crate::rand_ext;
+use crate::rand_ext::{self, MissionRng};
 use configparser::ini::Ini;
-use rand::{seq::SliceRandom, thread_rng};
+use rand::seq::SliceRandom;
 
-use crate::unit_db::{self as db, UnitDb, UnitType};
+use crate::terrain::Terrain;
+use crate::unit_db::{self as db, UnitDb, UnitQuery, UnitType};
+
+mod environment;
+mod formation;
+mod import;
+mod parse;
+mod placement;
+mod route;
+mod settings;
+mod validate;
+
+use environment::Environment;
+pub use environment::{Clouds, EnvironmentOptions, WindDirection};
+pub use formation::{FormationLayout, FormationShape, SpawnPositions};
+pub use import::{ImportReport, ImportedUnit, UnknownKey};
+use parse::{parse_named, InvalidValueError};
+pub use placement::{PlacementStrategy, SeparationOptions};
+use placement::{Placer, Side, TaskforcePlacer};
+pub use route::RouteStyle;
+pub use settings::{CrewSkill, Damage, MissionType, StationRole, UnitSettings, MAX_TELEGRAPH};
+pub use validate::Problem;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum WeaponState {
@@ -13,6 +36,13 @@ pub enum WeaponState {
     Hold,
 }
 
+impl WeaponState {
+    pub fn all() -> [Self; 3] {
+        use WeaponState::*;
+        [Free, Tight, Hold]
+    }
+}
+
 impl std::fmt::Display for WeaponState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use WeaponState::*;
@@ -25,39 +55,304 @@ impl std::fmt::Display for WeaponState {
     }
 }
 
+impl FromStr for WeaponState {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("weapon state", Self::all(), s)
+    }
+}
+
+/// The variant reference the game uses for a unit's standard fit.
+const DEFAULT_VARIANT: &str = "Default";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantOption {
+    /// The game default fit.
+    Default,
+    /// Any of the unit's variants, including the default.
+    Random,
+    /// A specific variant, falls back to the default if the unit doesn't
+    /// have it.
+    Variant(String),
+}
+
+impl VariantOption {
+    /// Resolve the variant reference to use for the given unit, any variant
+    /// is trusted for a unit that isn't in the unit database.
+    fn pick(&self, unit: Option<&db::Unit>, rng: &mut MissionRng) -> String {
+        match self {
+            Self::Default => DEFAULT_VARIANT.to_owned(),
+            Self::Random => unit
+                .map(|unit| unit.variants.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .map(String::as_str)
+                .chain(std::iter::once(DEFAULT_VARIANT))
+                .collect::<Vec<_>>()
+                .choose(rng)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| DEFAULT_VARIANT.to_owned()),
+            Self::Variant(variant) if unit.is_none_or(|unit| unit.variants.contains(variant)) => {
+                variant.clone()
+            }
+            Self::Variant(_) => DEFAULT_VARIANT.to_owned(),
+        }
+    }
+}
+
+impl std::fmt::Display for VariantOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "{DEFAULT_VARIANT}"),
+            Self::Random => write!(f, "<RANDOM>"),
+            Self::Variant(variant) => write!(f, "{variant}"),
+        }
+    }
+}
+
+/// How much ammunition and fuel a unit starts with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoresOption {
+    Full,
+    Depleted,
+    /// Either full or depleted.
+    Random,
+    /// Full stores with a specific loadout, falls back to the default
+    /// loadout if the unit doesn't have it.
+    ///
+    /// None of the sample missions have a loadout, so the `Loadout` key it
+    /// is written as is unconfirmed and the game may ignore it.
+    Loadout(String),
+}
+
+impl StoresOption {
+    /// Resolve the `Stores` and `Loadout` to use for the given unit, like
+    /// `VariantOption::pick` any loadout is trusted for an unknown unit.
+    fn pick(
+        &self,
+        unit: Option<&db::Unit>,
+        rng: &mut MissionRng,
+    ) -> (&'static str, Option<String>) {
+        match self {
+            Self::Full => ("Full", None),
+            Self::Depleted => ("Depleted", None),
+            Self::Random => {
+                let stores = ["Full", "Depleted"].choose(rng);
+                (stores.copied().unwrap_or("Full"), None)
+            }
+            Self::Loadout(loadout)
+                if unit.is_none_or(|unit| unit.metadata.loadouts.contains(loadout)) =>
+            {
+                ("Full", Some(loadout.clone()))
+            }
+            Self::Loadout(_) => ("Full", None),
+        }
+    }
+}
+
+impl std::fmt::Display for StoresOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => write!(f, "Full"),
+            Self::Depleted => write!(f, "Depleted"),
+            Self::Random => write!(f, "<RANDOM>"),
+            Self::Loadout(loadout) => write!(f, "{loadout}"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum UnitOption {
-    Unit(db::Unit),
+    Unit {
+        unit: Box<db::Unit>,
+        variant: VariantOption,
+        stores: StoresOption,
+        /// overrides the taskforce settings
+        settings: UnitSettings,
+    },
     Random {
-        nation: Option<db::Nation>,
-        utype: Option<UnitType>,
+        query: Box<UnitQuery>,
+        variant: VariantOption,
+        stores: StoresOption,
+        /// overrides the taskforce settings
+        settings: UnitSettings,
+    },
+    /// A unit of an imported mission, it stays where it was when the mission
+    /// is generated again.
+    Imported {
+        unit: Box<ImportedUnit>,
+        variant: VariantOption,
+        stores: StoresOption,
+        /// overrides the taskforce settings
+        settings: UnitSettings,
     },
 }
 
+impl UnitOption {
+    /// The unit in the unit database, `None` if it is random or an unknown
+    /// imported unit.
+    pub fn unit(&self) -> Option<&db::Unit> {
+        match self {
+            Self::Unit { unit, .. } => Some(unit),
+            Self::Random { .. } => None,
+            Self::Imported { unit, .. } => unit.unit.as_ref(),
+        }
+    }
+
+    pub fn variant(&self) -> &VariantOption {
+        match self {
+            Self::Unit { variant, .. }
+            | Self::Random { variant, .. }
+            | Self::Imported { variant, .. } => variant,
+        }
+    }
+
+    pub fn set_variant(&mut self, new_variant: VariantOption) {
+        match self {
+            Self::Unit { variant, .. }
+            | Self::Random { variant, .. }
+            | Self::Imported { variant, .. } => *variant = new_variant,
+        }
+    }
+
+    pub fn stores(&self) -> &StoresOption {
+        match self {
+            Self::Unit { stores, .. }
+            | Self::Random { stores, .. }
+            | Self::Imported { stores, .. } => stores,
+        }
+    }
+
+    pub fn set_stores(&mut self, new_stores: StoresOption) {
+        match self {
+            Self::Unit { stores, .. }
+            | Self::Random { stores, .. }
+            | Self::Imported { stores, .. } => *stores = new_stores,
+        }
+    }
+
+    pub fn settings(&self) -> &UnitSettings {
+        match self {
+            Self::Unit { settings, .. }
+            | Self::Random { settings, .. }
+            | Self::Imported { settings, .. } => settings,
+        }
+    }
+
+    pub fn set_settings(&mut self, new_settings: UnitSettings) {
+        match self {
+            Self::Unit { settings, .. }
+            | Self::Random { settings, .. }
+            | Self::Imported { settings, .. } => *settings = new_settings,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Unit {
     id: String,
+    variant: String,
+    stores: &'static str,
+    loadout: Option<String>,
     heading: u16,
     position: (f32, f32),
+    settings: UnitSettings,
+    /// positions relative to the map center, like `position`
+    waypoints: Vec<(f32, f32)>,
+    /// keys of an imported unit that aren't otherwise represented, written
+    /// back out unchanged
+    unknown_keys: Vec<(String, Option<String>)>,
 }
 
 impl Unit {
-    pub fn new(general: &GeneralOptions, id: &str) -> Self {
+    /// Create a unit at `position` with a random heading.
+    ///
+    /// Without a mission type in its `settings` the unit is given one suited
+    /// to its type.
+    pub fn new(
+        unit: &db::Unit,
+        position: (f32, f32),
+        variant: &VariantOption,
+        stores: &StoresOption,
+        mut settings: UnitSettings,
+        rng: &mut MissionRng,
+    ) -> Self {
+        settings.mission_type = settings
+            .mission_type
+            .or_else(|| Some(MissionType::default_for(unit)));
+        let (stores, loadout) = stores.pick(Some(unit), rng);
         Self {
-            id: id.to_owned(),
-            heading: rand_ext::heading(),
-            position: rand_ext::position(&general.size),
+            id: unit.id.clone(),
+            variant: variant.pick(Some(unit), rng),
+            stores,
+            loadout,
+            heading: rand_ext::heading(rng),
+            position,
+            settings,
+            waypoints: vec![],
+            unknown_keys: vec![],
+        }
+    }
+
+    /// Create an imported unit where it was, with the same heading and
+    /// waypoints.
+    fn imported(
+        imported: &ImportedUnit,
+        variant: &VariantOption,
+        stores: &StoresOption,
+        settings: UnitSettings,
+        rng: &mut MissionRng,
+    ) -> Self {
+        let unit = imported.unit.as_ref();
+        let (stores, loadout) = stores.pick(unit, rng);
+        Self {
+            id: imported.id.clone(),
+            variant: variant.pick(unit, rng),
+            stores,
+            loadout,
+            heading: imported.heading,
+            position: imported.position,
+            settings,
+            waypoints: imported.waypoints.clone(),
+            unknown_keys: imported.unknown_keys.clone(),
+        }
+    }
+
+    /// Give an escort its station role if it doesn't have one, suited to
+    /// what it is fitted with or else its mission. Only escorts are given
+    /// one, any set explicitly or imported are kept.
+    fn set_station_role(&mut self, metadata: Option<&db::UnitMetadata>) {
+        let settings = &mut self.settings;
+        settings.station_role = settings
+            .station_role
+            .or_else(|| metadata.and_then(StationRole::suited_to))
+            .or_else(|| settings.mission_type.and_then(StationRole::default_for));
+    }
+
+    /// Follow a route of the given style, turning to face the first
+    /// waypoint. Without one any waypoints, e.g. of an imported unit, are
+    /// kept.
+    fn set_route(&mut self, style: RouteStyle, size: &(u16, u16), rng: &mut MissionRng) {
+        if style == RouteStyle::None {
+            return;
+        }
+        self.waypoints = style.waypoints(self.position, size, rng);
+        if let Some(first) = self.waypoints.first() {
+            self.heading = route::bearing(self.position, *first);
         }
     }
 
     pub fn write_ini(&self, config: &mut Ini, section: &str) {
         config.set(section, "Type", Some(self.id.clone()));
-        // speed setting
-        config.set(section, "Telegraph", Some(3.to_string()));
-        // defaults to "Green"
-        config.set(section, "CrewSkill", Some("Trained".into()));
+        config.set(section, "VariantReference", Some(self.variant.clone()));
+        self.settings.write_ini(config, section);
         // defaults to "Depleted"
-        config.set(section, "Stores", Some("Full".into()));
+        config.set(section, "Stores", Some(self.stores.into()));
+        // unconfirmed, see `StoresOption::Loadout`
+        if let Some(loadout) = &self.loadout {
+            config.set(section, "Loadout", Some(loadout.clone()));
+        }
 
         // generate our own positions and headings, don't use RandomSpawn*
         // because by having static positions the player can replay the mission
@@ -71,50 +366,135 @@ impl Unit {
             let (x, y) = self.position;
             config.set(section, "RelativePositionInNM", Some(format!("{x},0,{y}")));
         }
+        // unconfirmed, see `route`
+        if !self.waypoints.is_empty() {
+            config.set(
+                section,
+                "NumberOfWaypoints",
+                Some(self.waypoints.len().to_string()),
+            );
+            for (idx, (x, y)) in self.waypoints.iter().enumerate() {
+                let key = format!("Waypoint{}", idx + 1);
+                config.set(section, &key, Some(format!("{x},0,{y}")));
+            }
+        }
+        // last, so that they take precedence over anything generated
+        for (key, value) in &self.unknown_keys {
+            config.set(section, key, value.clone());
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct FormationOption {
     pub units: Vec<UnitOption>,
+    pub layout: FormationLayout,
+    /// the index of the guide in `units`, the rest escort it. Without one
+    /// the largest unit is picked.
+    pub leader: Option<usize>,
+    /// followed by the leader, the rest keep formation with it
+    pub route: RouteStyle,
+}
+
+impl FormationOption {
+    pub fn new(units: Vec<UnitOption>) -> Self {
+        Self {
+            units,
+            layout: FormationLayout::default(),
+            leader: None,
+            route: RouteStyle::None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct TaskforceOptions {
     pub weapon_state: WeaponState,
+    /// the settings of every unit, unless the unit overrides them
+    pub settings: UnitSettings,
     pub units: Vec<UnitOption>,
+    /// the route each of the lone `units` follows
+    pub route: RouteStyle,
+    pub placement: PlacementStrategy,
     pub formations: Vec<FormationOption>,
 }
 
 type UnitReference = (UnitType, usize);
 
+#[derive(Debug)]
+struct Formation {
+    units: Vec<UnitReference>,
+    layout: FormationLayout,
+}
+
 #[derive(Debug)]
 pub struct Taskforce {
     options: TaskforceOptions,
     name: String,
-    units: HashMap<UnitType, Vec<Unit>>,
-    formations: Vec<Vec<UnitReference>>,
+    units: BTreeMap<UnitType, Vec<Unit>>,
+    formations: Vec<Formation>,
 }
 
 impl Taskforce {
-    pub fn new(
+    fn new(
         unit_db: &UnitDb,
         general: &GeneralOptions,
         name: &str,
         options: TaskforceOptions,
+        placer: &mut TaskforcePlacer,
+        rng: &mut MissionRng,
     ) -> Self {
-        let mut units = HashMap::new();
+        let mut units = BTreeMap::new();
         // insert lone units (outside of formation)
-        insert_units(unit_db, general, &mut units, &options.units);
+        let lone_units = insert_units(
+            unit_db,
+            general,
+            &options.settings,
+            &mut units,
+            &options.units,
+            placer,
+            rng,
+        );
+        for reference in lone_units.into_iter().flatten() {
+            set_route(&mut units, reference, options.route, general, rng);
+        }
 
         let mut formations = Vec::new();
         for formation_opt in &options.formations {
-            formations.push(insert_units(
+            let inserted = insert_units(
                 unit_db,
                 general,
+                &options.settings,
                 &mut units,
                 &formation_opt.units,
-            ));
+                placer,
+                rng,
+            );
+            let leader = formation_opt
+                .leader
+                .and_then(|idx| inserted.get(idx).copied().flatten())
+                .or_else(|| high_value_unit(unit_db, &units, inserted.iter().flatten()));
+            // the leader is first in the formation string, followed by its
+            // escorts
+            let mut formation_units: Vec<_> = inserted.into_iter().flatten().collect();
+            if let Some(leader) = leader {
+                formation_units.retain(|reference| *reference != leader);
+                formation_units.insert(0, leader);
+            }
+            // only escorts are given a station
+            for reference in formation_units.iter().skip(1) {
+                if let Some(unit) = unit_mut(&mut units, *reference) {
+                    let metadata = unit_db.by_id(&unit.id).map(|unit| &unit.metadata);
+                    unit.set_station_role(metadata);
+                }
+            }
+            if let Some(leader) = formation_units.first() {
+                set_route(&mut units, *leader, formation_opt.route, general, rng);
+            }
+            formations.push(Formation {
+                units: formation_units,
+                layout: formation_opt.layout.clone(),
+            });
         }
 
         Self {
@@ -126,8 +506,14 @@ impl Taskforce {
     }
 
     pub fn write_ini(&self, config: &mut Ini) {
-        for (utype, units) in &self.units {
-            // setup NumberOf<TYPE><TASKFORCE>
+        for utype in UnitType::all() {
+            let units = self
+                .units
+                .get(&utype)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            // setup NumberOf<TYPE><TASKFORCE>, even if there are none so
+            // that the game doesn't fall back to any in the template
             config.set(
                 "Mission",
                 &format!("NumberOf{}{}", self.name, utype.calitalised_plural()),
@@ -138,12 +524,13 @@ impl Taskforce {
             for (idx, unit) in units.iter().enumerate() {
                 let utype = utype.capitalised_singular();
                 let section = format!("{}{utype}{}", self.name, idx + 1);
-                unit.write_ini(config, &section);
+                // first, an imported unit may have its own
                 config.set(
                     &section,
                     "WeaponStatus",
                     Some(self.options.weapon_state.to_string()),
                 );
+                unit.write_ini(config, &section);
             }
         }
 
@@ -164,55 +551,191 @@ impl Taskforce {
     }
 }
 
+/// Insert the units of `unit_opts`, returning a reference to each, `None` for
+/// any that couldn't be inserted.
 fn insert_units(
     unit_db: &UnitDb,
     general: &GeneralOptions,
-    units: &mut HashMap<UnitType, Vec<Unit>>,
+    settings: &UnitSettings,
+    units: &mut BTreeMap<UnitType, Vec<Unit>>,
     unit_opts: &[UnitOption],
-) -> Vec<UnitReference> {
+    placer: &mut TaskforcePlacer,
+    rng: &mut MissionRng,
+) -> Vec<Option<UnitReference>> {
     unit_opts
         .iter()
-        .filter_map(|unit_opt| match unit_opt {
-            UnitOption::Unit(unit) => unit_db
+        .map(|unit_opt| match unit_opt {
+            UnitOption::Unit { unit, .. } => unit_db
                 .by_id(&unit.id)
-                // TODO: fail if not found
-                .map(|unit| insert_unit(general, units, unit)),
-            UnitOption::Random { nation, utype } => {
-                let matches = unit_db.search(nation.clone(), *utype);
+                // unknown units are reported by `MissionOptions::validate`
+                .and_then(|unit| insert_unit(settings, units, unit, unit_opt, placer, rng)),
+            UnitOption::Random { query, .. } => {
+                // never pick units that weren't around at the time
+                let query = (**query).clone().in_service(general.date.year);
+                let mut matches = unit_db.search(&query);
+                matches.retain(|unit| general.can_place(unit.utype));
                 matches
-                    .choose(&mut thread_rng())
-                    .map(|unit| insert_unit(general, units, unit))
+                    .choose(rng)
+                    .and_then(|unit| insert_unit(settings, units, unit, unit_opt, placer, rng))
+            }
+            UnitOption::Imported {
+                unit: imported,
+                variant,
+                stores,
+                settings: unit_settings,
+            } => {
+                placer.fixed(imported.position);
+                let settings = settings.overlay(unit_settings);
+                let new_unit = Unit::imported(imported, variant, stores, settings, rng);
+                let unit_list = units.entry(imported.utype).or_default();
+                unit_list.push(new_unit);
+                Some((imported.utype, unit_list.len() - 1))
             }
         })
         .collect()
 }
 
+/// Insert a unit, with the `settings` of its taskforce, returning `None` if
+/// it couldn't be placed.
+///
+/// Land units without a land area are reported by `MissionOptions::validate`.
 fn insert_unit(
-    general: &GeneralOptions,
-    units: &mut HashMap<UnitType, Vec<Unit>>,
+    settings: &UnitSettings,
+    units: &mut BTreeMap<UnitType, Vec<Unit>>,
     unit: &db::Unit,
-) -> UnitReference {
+    unit_opt: &UnitOption,
+    placer: &mut TaskforcePlacer,
+    rng: &mut MissionRng,
+) -> Option<UnitReference> {
+    let position = placer.place(unit, rng)?;
+    let settings = settings.overlay(unit_opt.settings());
+    let variant = unit_opt.variant();
+    let stores = unit_opt.stores();
+    let new_unit = Unit::new(unit, position, variant, stores, settings, rng);
     let unit_list = units.entry(unit.utype).or_default();
     let index = unit_list.len();
-    unit_list.push(Unit::new(general, &unit.id));
-    (unit.utype, index)
+    unit_list.push(new_unit);
+    Some((unit.utype, index))
+}
+
+fn unit_mut(
+    units: &mut BTreeMap<UnitType, Vec<Unit>>,
+    (utype, idx): UnitReference,
+) -> Option<&mut Unit> {
+    units.get_mut(&utype).and_then(|units| units.get_mut(idx))
 }
 
-fn formation_str(taskforce: &str, formation: &[UnitReference]) -> String {
+fn set_route(
+    units: &mut BTreeMap<UnitType, Vec<Unit>>,
+    reference: UnitReference,
+    style: RouteStyle,
+    general: &GeneralOptions,
+    rng: &mut MissionRng,
+) {
+    // land units stay where they are
+    if reference.0 == UnitType::LandUnit {
+        return;
+    }
+    if let Some(unit) = unit_mut(units, reference) {
+        unit.set_route(style, &general.size, rng);
+    }
+}
+
+/// The largest of the `formation` units, the first if none are larger, to
+/// be guarded by the others.
+fn high_value_unit<'a>(
+    unit_db: &UnitDb,
+    units: &BTreeMap<UnitType, Vec<Unit>>,
+    formation: impl Iterator<Item = &'a UnitReference>,
+) -> Option<UnitReference> {
+    let displacement = |(utype, idx): &UnitReference| {
+        let unit = units.get(utype)?.get(*idx)?;
+        unit_db.by_id(&unit.id)?.metadata.displacement
+    };
+    // the minimum is the first of any equal
+    formation
+        .min_by_key(|reference| Reverse(displacement(reference)))
+        .copied()
+}
+
+fn formation_str(taskforce: &str, formation: &Formation) -> String {
     let sections = formation
+        .units
         .iter()
         .map(|(utype, idx)| {
             let utype = utype.capitalised_singular();
             format!("{taskforce}{utype}{}", idx + 1)
         })
         .collect::<Vec<_>>();
-    let formation = sections.join(",");
-    // OverrideSpawnPositions allows us to place our units anywhere and the formation
-    // will be adjusted by the game on mission start (which is exactly what we want)
-    //
-    // use 7.5nm spacing for now, this may not be optimal in tight spaces, but this mod is currently
-    // unable to handle those kind of scenarios anyway.
-    format!("{formation}|Unnamed Group|Circle|7.5|OverrideSpawnPositions")
+    format!("{}|{}", sections.join(","), formation.layout)
+}
+
+/// A calendar date, formatted like the game does, e.g. `1985,6,26`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// The number of days in `month` of `year`, `None` if there is no such
+    /// month.
+    fn days_in_month(year: u16, month: u8) -> Option<u8> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+            4 | 6 | 9 | 11 => Some(30),
+            2 if leap => Some(29),
+            2 => Some(28),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = InvalidValueError;
+
+    /// Parse a date separated by commas, dashes or slashes, e.g. `1985-6-26`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || InvalidValueError::new("date", s);
+        let mut parts = s.trim().split([',', '-', '/']).map(str::trim);
+        let mut next = || parts.next().ok_or_else(error);
+        let year = next()?.parse().map_err(|_| error())?;
+        let month = next()?.parse().map_err(|_| error())?;
+        let day = next()?.parse().map_err(|_| error())?;
+        let days = Self::days_in_month(year, month).ok_or_else(error)?;
+        if parts.next().is_some() || !(1..=days).contains(&day) {
+            return Err(error());
+        }
+        Ok(Self { year, month, day })
+    }
+}
+
+/// A box on land, relative to the map center, that land units are placed in.
+///
+/// The mission box is assumed to be open sea, so land units need an area of
+/// their own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandArea {
+    /// the center of the area (x,y) in nm from the map center.
+    pub offset: (f32, f32),
+    /// the size of the area (w,h) in nm.
+    pub size: (u16, u16),
+}
+
+impl LandArea {
+    fn position(&self, rng: &mut MissionRng) -> (f32, f32) {
+        let (x, y) = rand_ext::position(rng, &self.size);
+        (self.offset.0 + x, self.offset.1 + y)
+    }
 }
 
 /// Mission wide options
@@ -222,15 +745,35 @@ pub struct GeneralOptions {
     pub latlon: (f32, f32),
     /// the size of the box (w,h) that the mission will take place in.
     pub size: (u16, u16),
+    /// the date the mission takes place on, only units in service at the
+    /// time are picked for randoms.
+    pub date: Date,
+    /// where land units are placed, there can't be any without it.
+    pub land: Option<LandArea>,
+    pub separation: SeparationOptions,
+}
+
+impl GeneralOptions {
+    /// Whether units of the given type can be placed, land units need a land
+    /// area.
+    fn can_place(&self, utype: UnitType) -> bool {
+        utype != UnitType::LandUnit || self.land.is_some()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct MissionOptions {
     pub general: GeneralOptions,
+    pub environment: EnvironmentOptions,
     pub neutral: TaskforceOptions,
     pub blue: TaskforceOptions,
     pub red: TaskforceOptions,
-    // TODO: add environment config
+    /// the same seed, options and units always generate the same mission,
+    /// a random seed is used if there is none.
+    pub seed: Option<u64>,
+    /// keys of an imported mission that aren't otherwise represented in the
+    /// options, they are written back out unchanged.
+    pub unknown_keys: Vec<UnknownKey>,
 }
 
 impl Default for MissionOptions {
@@ -240,67 +783,300 @@ impl Default for MissionOptions {
                 // somewhere in the eastern med
                 latlon: (34.31, 29.62),
                 size: (150, 150),
+                date: Date {
+                    year: 1985,
+                    month: 6,
+                    day: 26,
+                },
+                land: None,
+                separation: SeparationOptions::default(),
             },
+            environment: EnvironmentOptions::default(),
             neutral: TaskforceOptions {
                 weapon_state: WeaponState::Hold,
+                settings: UnitSettings::taskforce_default(),
                 units: vec![],
+                route: RouteStyle::None,
+                placement: PlacementStrategy::Uniform,
                 formations: vec![],
             },
             blue: TaskforceOptions {
                 weapon_state: WeaponState::Tight,
+                settings: UnitSettings::taskforce_default(),
                 units: vec![],
+                route: RouteStyle::None,
+                placement: PlacementStrategy::Uniform,
                 formations: vec![],
             },
             red: TaskforceOptions {
                 weapon_state: WeaponState::Free,
+                settings: UnitSettings::taskforce_default(),
                 units: vec![],
+                route: RouteStyle::None,
+                placement: PlacementStrategy::Uniform,
                 formations: vec![],
             },
+            seed: None,
+            unknown_keys: vec![],
         }
     }
 }
 
+// the section prefixes the game uses for each taskforce
+const NEUTRAL: &str = "Neutral";
+const BLUE: &str = "Taskforce1";
+const RED: &str = "Taskforce2";
+
 #[derive(Debug)]
 pub struct Mission {
     options: MissionOptions,
+    environment: Environment,
     neutral: Taskforce,
     blue: Taskforce,
     red: Taskforce,
+    /// anything wrong found while generating, see `Mission::problems`
+    problems: Vec<Problem>,
 }
 
 impl Mission {
-    pub fn new(unit_db: &UnitDb, options: MissionOptions) -> Self {
-        let neutral = Taskforce::new(
-            unit_db,
-            &options.general,
-            "Neutral",
-            options.neutral.clone(),
-        );
-        let blue = Taskforce::new(
-            unit_db,
-            &options.general,
-            "Taskforce1",
-            options.blue.clone(),
-        );
-        let red = Taskforce::new(unit_db, &options.general, "Taskforce2", options.red.clone());
+    /// Generate a mission, ships are kept off any land in the `terrain`.
+    pub fn new(unit_db: &UnitDb, terrain: &Terrain, options: MissionOptions) -> Self {
+        let seed = options.seed.unwrap_or_else(rand_ext::random_seed);
+        let mut rng = rand_ext::seeded(seed);
+        let mut placer = Placer::new(&options.general, terrain, &mut rng);
+        let mut taskforce = |side, name, taskforce: &TaskforceOptions| {
+            let mut placer = placer.taskforce(side, taskforce.placement, &mut rng);
+            Taskforce::new(
+                unit_db,
+                &options.general,
+                name,
+                taskforce.clone(),
+                &mut placer,
+                &mut rng,
+            )
+        };
+        let neutral = taskforce(Side::Neutral, NEUTRAL, &options.neutral);
+        let blue = taskforce(Side::Blue, BLUE, &options.blue);
+        let red = taskforce(Side::Red, RED, &options.red);
+        // after the units, so that changing the environment doesn't change
+        // which units are generated
+        let environment = options.environment.pick(options.general.date, &mut rng);
+        let problems = placer.problems;
         Self {
             options,
+            environment,
             neutral,
             blue,
             red,
+            problems,
         }
     }
 
+    /// Problems only found while generating, unlike those of
+    /// `MissionOptions::validate` these are warnings.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
     pub fn write_ini(&self, config: &mut Ini) {
         self.write_environment(config);
         self.neutral.write_ini(config);
         self.blue.write_ini(config);
         self.red.write_ini(config);
+        // last, so that they take precedence over anything generated
+        for unknown in &self.options.unknown_keys {
+            config.set(&unknown.section, &unknown.key, unknown.value.clone());
+        }
     }
 
     fn write_environment(&self, config: &mut Ini) {
         let (lat, lon) = self.options.general.latlon;
         config.set("Environment", "MapCenterLatitude", Some(lat.to_string()));
         config.set("Environment", "MapCenterLongitude", Some(lon.to_string()));
+        self.environment.write_ini(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_stores() {
+        let unit_db = UnitDb::fixture();
+        let unit = unit_db.by_id("usn_f14a").unwrap();
+        let rng = &mut rand_ext::seeded(0);
+        assert_eq!(
+            StoresOption::Depleted.pick(Some(unit), rng),
+            ("Depleted", None)
+        );
+        let loadout = StoresOption::Loadout("Fleet Defence".into());
+        assert_eq!(
+            loadout.pick(Some(unit), rng),
+            ("Full", Some("Fleet Defence".into()))
+        );
+        // unknown loadouts fall back to the default
+        let loadout = StoresOption::Loadout("Strike".into());
+        assert_eq!(loadout.pick(Some(unit), rng), ("Full", None));
+    }
+
+    #[test]
+    fn test_pick_variant() {
+        let unit_db = UnitDb::fixture();
+        let unit = unit_db.by_id("usn_f14a").unwrap();
+        let rng = &mut rand_ext::seeded(0);
+        assert_eq!(VariantOption::Default.pick(Some(unit), rng), "Default");
+        assert_eq!(
+            VariantOption::Variant("Late".into()).pick(Some(unit), rng),
+            "Late"
+        );
+        assert_eq!(
+            VariantOption::Variant("Early".into()).pick(Some(unit), rng),
+            "Default"
+        );
+        // nothing is known about the variants of an unknown unit
+        assert_eq!(
+            VariantOption::Variant("Early".into()).pick(None, rng),
+            "Early"
+        );
+        assert_eq!(VariantOption::Random.pick(None, rng), "Default");
+    }
+
+    fn generate(unit_db: &UnitDb, options: &MissionOptions) -> String {
+        let mut config = Ini::new_cs();
+        Mission::new(unit_db, &Terrain::default(), options.clone()).write_ini(&mut config);
+        config.writes()
+    }
+
+    #[test]
+    fn test_seeded_mission() {
+        let unit_db = UnitDb::fixture();
+        let random = UnitOption::Random {
+            query: Box::new(UnitQuery::new().utype(UnitType::Vessel)),
+            variant: VariantOption::Random,
+            stores: StoresOption::Random,
+            settings: UnitSettings::default(),
+        };
+        let mut options = MissionOptions {
+            seed: Some(42),
+            ..Default::default()
+        };
+        options.blue.units = vec![random.clone(); 3];
+        options.red.formations = vec![FormationOption::new(vec![random; 2])];
+
+        let mission = generate(&unit_db, &options);
+        assert_eq!(mission, generate(&unit_db, &options));
+        options.seed = Some(43);
+        assert_ne!(mission, generate(&unit_db, &options));
+    }
+
+    /// The same seed must give the same mission on every platform and
+    /// version, so that shared seeds keep working.
+    #[test]
+    fn test_seeded_mission_pinned() {
+        let unit_db = UnitDb::fixture();
+        let random = UnitOption::Random {
+            query: Box::new(UnitQuery::new().utype(UnitType::Vessel)),
+            variant: VariantOption::Random,
+            stores: StoresOption::Full,
+            settings: UnitSettings::default(),
+        };
+        let mut options = MissionOptions {
+            seed: Some(42),
+            ..Default::default()
+        };
+        options.blue.units = vec![random; 3];
+
+        let mut config = Ini::new_cs();
+        Mission::new(&unit_db, &Terrain::default(), options).write_ini(&mut config);
+        let unit = |section: &str| {
+            [
+                "Type",
+                "VariantReference",
+                "Heading",
+                "RelativePositionInNM",
+            ]
+            .map(|key| config.get(section, key).unwrap_or_default())
+        };
+        assert_eq!(
+            unit("Taskforce1Vessel1"),
+            ["wp_bpk_kashin", "Default", "123", "-53.042076,0,67.541306"]
+        );
+        assert_eq!(
+            unit("Taskforce1Vessel2"),
+            ["wp_bpk_kashin", "Default", "113", "35.573418,0,-31.71093"]
+        );
+        assert_eq!(
+            unit("Taskforce1Vessel3"),
+            ["usn_dd_spruance", "Default", "297", "45.580917,0,19.221611"]
+        );
+    }
+
+    #[test]
+    fn test_formation_leader() {
+        let unit_db = UnitDb::fixture();
+        // escorts take their role from what they are fitted with, or else
+        // the mission they are given
+        let unit = |id, mission_type| UnitOption::Unit {
+            unit: Box::new(unit_db.by_id(id).unwrap().clone()),
+            variant: VariantOption::Default,
+            stores: StoresOption::Full,
+            settings: UnitSettings {
+                mission_type: Some(mission_type),
+                ..Default::default()
+            },
+        };
+        let mut options = MissionOptions::default();
+        let formation = FormationOption::new(vec![
+            unit("wp_bpk_kashin", MissionType::Asw),
+            unit("usn_dd_spruance", MissionType::NoMission),
+        ]);
+        options.blue.formations = vec![formation];
+
+        // the spruance is the largest
+        let mut config = Ini::new_cs();
+        Mission::new(&unit_db, &Terrain::default(), options.clone()).write_ini(&mut config);
+        let formation = config.get("Mission", "Taskforce1_Formation1").unwrap();
+        assert!(formation.starts_with("Taskforce1Vessel2,Taskforce1Vessel1|"));
+        assert_eq!(config.get("Taskforce1Vessel2", "StationRole"), None);
+        assert_eq!(
+            config.get("Taskforce1Vessel1", "StationRole").as_deref(),
+            Some("ASW")
+        );
+
+        options.blue.formations[0].leader = Some(0);
+        let mut config = Ini::new_cs();
+        Mission::new(&unit_db, &Terrain::default(), options).write_ini(&mut config);
+        let formation = config.get("Mission", "Taskforce1_Formation1").unwrap();
+        assert!(formation.starts_with("Taskforce1Vessel1,Taskforce1Vessel2|"));
+        // the spruance has a sonar
+        assert_eq!(
+            config.get("Taskforce1Vessel2", "StationRole").as_deref(),
+            Some("ASW")
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = Date {
+            year: 1985,
+            month: 6,
+            day: 26,
+        };
+        assert_eq!("1985,6,26".parse::<Date>().ok(), Some(date));
+        assert_eq!("1985-06-26".parse::<Date>().ok(), Some(date));
+        assert_eq!(" 1985 / 6 / 26 ".parse::<Date>().ok(), Some(date));
+        assert_eq!(date.to_string(), "1985,6,26");
+        assert!("1985,6".parse::<Date>().is_err());
+        assert!("1985,13,1".parse::<Date>().is_err());
+        assert!("1985,2,31".parse::<Date>().is_err());
+        assert!("1985,4,31".parse::<Date>().is_err());
+        assert!("1985,2,29".parse::<Date>().is_err());
+        assert!("1984,2,29".parse::<Date>().is_ok());
+        assert!("1900,2,29".parse::<Date>().is_err());
+        assert!("2000,2,29".parse::<Date>().is_ok());
+        assert!("1985,12,31".parse::<Date>().is_ok());
+        assert!("1985,6,26,1".parse::<Date>().is_err());
+        assert!("".parse::<Date>().is_err());
     }
 }
@@ -0,0 +1,295 @@
+//! The time and weather a mission takes place in.
+
+use std::str::FromStr;
+
+use configparser::ini::Ini;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::parse::{parse_named, InvalidValueError};
+use super::Date;
+use crate::rand_ext::MissionRng;
+
+/// A time of day, formatted like the game does, e.g. `10,0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Time {
+    fn random(rng: &mut MissionRng) -> Self {
+        Self {
+            hour: rng.gen_range(0..24),
+            minute: rng.gen_range(0..60),
+        }
+    }
+}
+
+impl std::fmt::Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.hour, self.minute)
+    }
+}
+
+impl FromStr for Time {
+    type Err = InvalidValueError;
+
+    /// Parse a time separated by a comma or colon, e.g. `22:30`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || InvalidValueError::new("time", s);
+        let (hour, minute) = s.trim().split_once([',', ':']).ok_or_else(error)?;
+        let hour = hour.trim().parse().map_err(|_| error())?;
+        let minute = minute.trim().parse().map_err(|_| error())?;
+        if hour >= 24 || minute >= 60 {
+            return Err(error());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+/// How rough the sea is, from 0 (calm) to 9 (phenomenal).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SeaState(u8);
+
+impl SeaState {
+    const MAX: u8 = 9;
+
+    fn random(rng: &mut MissionRng) -> Self {
+        // anything above very rough (6) is rarely wanted by chance
+        Self(rng.gen_range(0..=6))
+    }
+}
+
+impl std::fmt::Display for SeaState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for SeaState {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(state) if state <= Self::MAX => Ok(Self(state)),
+            _ => Err(InvalidValueError::new("sea state", s)),
+        }
+    }
+}
+
+/// Cloud cover, from none to full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Clouds {
+    Clear,
+    Few,
+    Scattered,
+    Broken,
+    Overcast,
+}
+
+impl Clouds {
+    pub fn all() -> [Self; 5] {
+        use Clouds::*;
+        [Clear, Few, Scattered, Broken, Overcast]
+    }
+
+    fn random(rng: &mut MissionRng) -> Self {
+        *Self::all().choose(rng).unwrap_or(&Self::Clear)
+    }
+}
+
+impl std::fmt::Display for Clouds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Clouds::*;
+        let str = match self {
+            Clear => "Clear",
+            Few => "Few",
+            Scattered => "Scattered",
+            Broken => "Broken",
+            Overcast => "Overcast",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for Clouds {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("cloud cover", Self::all(), s)
+    }
+}
+
+/// The compass point the wind blows from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WindDirection {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl WindDirection {
+    pub fn all() -> [Self; 8] {
+        use WindDirection::*;
+        [N, NE, E, SE, S, SW, W, NW]
+    }
+
+    fn random(rng: &mut MissionRng) -> Self {
+        *Self::all().choose(rng).unwrap_or(&Self::N)
+    }
+}
+
+impl std::fmt::Display for WindDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use WindDirection::*;
+        let str = match self {
+            N => "N",
+            NE => "NE",
+            E => "E",
+            SE => "SE",
+            S => "S",
+            SW => "SW",
+            W => "W",
+            NW => "NW",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for WindDirection {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("wind direction", Self::all(), s)
+    }
+}
+
+/// The time and weather, any that are `None` are picked at random.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentOptions {
+    /// replace the day and month of the mission date with random ones, the
+    /// year is kept since it decides which units are in service.
+    pub random_day: bool,
+    pub time: Option<Time>,
+    pub sea_state: Option<SeaState>,
+    pub clouds: Option<Clouds>,
+    pub wind_direction: Option<WindDirection>,
+}
+
+impl Default for EnvironmentOptions {
+    /// The same as the mission template.
+    fn default() -> Self {
+        Self {
+            random_day: false,
+            time: Some(Time {
+                hour: 10,
+                minute: 0,
+            }),
+            sea_state: Some(SeaState(4)),
+            clouds: Some(Clouds::Scattered),
+            wind_direction: Some(WindDirection::N),
+        }
+    }
+}
+
+impl EnvironmentOptions {
+    /// Pick any random values, on the mission `date`.
+    pub fn pick(&self, date: Date, rng: &mut MissionRng) -> Environment {
+        let date = match self.random_day {
+            true => Date {
+                // every month has at least 28 days
+                month: rng.gen_range(1..=12),
+                day: rng.gen_range(1..=28),
+                ..date
+            },
+            false => date,
+        };
+        Environment {
+            date,
+            time: self.time.unwrap_or_else(|| Time::random(rng)),
+            sea_state: self.sea_state.unwrap_or_else(|| SeaState::random(rng)),
+            clouds: self.clouds.unwrap_or_else(|| Clouds::random(rng)),
+            wind_direction: self
+                .wind_direction
+                .unwrap_or_else(|| WindDirection::random(rng)),
+        }
+    }
+}
+
+/// The environment of a generated mission.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Environment {
+    date: Date,
+    time: Time,
+    sea_state: SeaState,
+    clouds: Clouds,
+    wind_direction: WindDirection,
+}
+
+impl Environment {
+    pub fn write_ini(&self, config: &mut Ini) {
+        let mut set = |key: &str, value: String| config.set("Environment", key, Some(value));
+        set("Date", self.date.to_string());
+        set("Time", self.time.to_string());
+        set("SeaState", self.sea_state.to_string());
+        set("Clouds", self.clouds.to_string());
+        set("WindDirection", self.wind_direction.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand_ext;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "22:30".parse::<Time>().ok(),
+            Some(Time {
+                hour: 22,
+                minute: 30
+            })
+        );
+        assert_eq!("10,0".parse::<Time>().unwrap().to_string(), "10,0");
+        assert!("24,0".parse::<Time>().is_err());
+        assert!("10".parse::<Time>().is_err());
+        assert_eq!("9".parse::<SeaState>().ok(), Some(SeaState(9)));
+        assert!("10".parse::<SeaState>().is_err());
+        assert_eq!("overcast".parse::<Clouds>().ok(), Some(Clouds::Overcast));
+        assert!("Foggy".parse::<Clouds>().is_err());
+        assert_eq!("SW".parse::<WindDirection>().ok(), Some(WindDirection::SW));
+    }
+
+    #[test]
+    fn test_pick() {
+        let date = Date {
+            year: 1985,
+            month: 6,
+            day: 26,
+        };
+        let rng = &mut rand_ext::seeded(0);
+        let fixed = EnvironmentOptions::default();
+        let environment = fixed.pick(date, rng);
+        assert_eq!(environment.date, date);
+        assert_eq!(environment.sea_state, SeaState(4));
+
+        let random = EnvironmentOptions {
+            random_day: true,
+            time: None,
+            sea_state: None,
+            clouds: None,
+            wind_direction: None,
+        };
+        let environment = random.pick(date, rng);
+        assert_eq!(environment.date.year, 1985);
+        assert!(environment.sea_state.0 <= 6);
+        assert!(environment.date.day <= 28);
+    }
+}
@@ -0,0 +1,183 @@
+//! How the units of a formation are arranged, everything after the member
+//! list of a formation string, e.g. `Unnamed Group|Circle|7.5|OverrideSpawnPositions`.
+
+use std::str::FromStr;
+
+use super::parse::{parse_named, InvalidValueError};
+
+/// The shape the units of a formation keep around the first unit.
+///
+/// Only the shapes seen in missions saved by the game are known, any others,
+/// imported or entered by the user, are kept as they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormationShape {
+    Circle,
+    Other(String),
+}
+
+impl FormationShape {
+    pub fn all() -> [Self; 1] {
+        [Self::Circle]
+    }
+}
+
+impl std::fmt::Display for FormationShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Circle => "Circle",
+            Self::Other(shape) => shape,
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for FormationShape {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err(InvalidValueError::new("formation shape", s)),
+            shape => Ok(parse_named("formation shape", Self::all(), shape)
+                .unwrap_or_else(|_| Self::Other(shape.to_owned()))),
+        }
+    }
+}
+
+/// Where the units of a formation start the mission.
+///
+/// Like the shape, only those seen in missions saved by the game are known.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpawnPositions {
+    /// the game moves the units into formation on mission start, wherever
+    /// they were placed
+    Override,
+    Other(String),
+}
+
+impl SpawnPositions {
+    pub fn all() -> [Self; 1] {
+        [Self::Override]
+    }
+}
+
+impl std::fmt::Display for SpawnPositions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Override => "OverrideSpawnPositions",
+            Self::Other(spawn) => spawn,
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for SpawnPositions {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err(InvalidValueError::new("spawn positions", s)),
+            spawn => Ok(parse_named("spawn positions", Self::all(), spawn)
+                .unwrap_or_else(|_| Self::Other(spawn.to_owned()))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormationLayout {
+    /// shown in game, it doesn't need to be unique
+    pub name: String,
+    pub shape: FormationShape,
+    /// the distance between units in nm
+    pub spacing: f32,
+    pub spawn_positions: SpawnPositions,
+}
+
+impl Default for FormationLayout {
+    /// OverrideSpawnPositions allows us to place our units anywhere and the
+    /// formation will be adjusted by the game on mission start (which is
+    /// exactly what we want).
+    ///
+    /// Use 7.5nm spacing for now, this may not be optimal in tight spaces,
+    /// but this mod is currently unable to handle those kind of scenarios
+    /// anyway.
+    fn default() -> Self {
+        Self {
+            name: "Unnamed Group".into(),
+            shape: FormationShape::Circle,
+            spacing: 7.5,
+            spawn_positions: SpawnPositions::Override,
+        }
+    }
+}
+
+impl std::fmt::Display for FormationLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}",
+            self.name, self.shape, self.spacing, self.spawn_positions
+        )
+    }
+}
+
+impl FromStr for FormationLayout {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || InvalidValueError::new("formation layout", s);
+        let parts: Vec<&str> = s.split('|').map(str::trim).collect();
+        let [name, shape, spacing, spawn] = parts[..] else {
+            return Err(error());
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            shape: shape.parse().map_err(|_| error())?,
+            spacing: spacing.parse().map_err(|_| error())?,
+            spawn_positions: spawn.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = FormationLayout::default();
+        assert_eq!(
+            layout.to_string(),
+            "Unnamed Group|Circle|7.5|OverrideSpawnPositions"
+        );
+
+        // from the formation sample
+        let layout: FormationLayout = "Group Name 1|Circle|1.5|OverrideSpawnPositions"
+            .parse()
+            .unwrap();
+        assert_eq!(layout.name, "Group Name 1");
+        assert_eq!(layout.shape, FormationShape::Circle);
+        assert_eq!(layout.spacing, 1.5);
+        assert_eq!(layout.spawn_positions, SpawnPositions::Override);
+        assert_eq!(
+            layout.to_string().parse::<FormationLayout>().ok(),
+            Some(layout)
+        );
+
+        // unknown shapes and spawn positions are kept as they are
+        let layout: FormationLayout = "Group|Blob|7.5|Anywhere".parse().unwrap();
+        assert_eq!(layout.shape, FormationShape::Other("Blob".into()));
+        assert_eq!(
+            layout.spawn_positions,
+            SpawnPositions::Other("Anywhere".into())
+        );
+        assert_eq!(layout.to_string(), "Group|Blob|7.5|Anywhere");
+
+        assert!("Group|Circle|7.5".parse::<FormationLayout>().is_err());
+        assert!("Group|Circle|wide|OverrideSpawnPositions"
+            .parse::<FormationLayout>()
+            .is_err());
+        assert!("Group||7.5|OverrideSpawnPositions"
+            .parse::<FormationLayout>()
+            .is_err());
+    }
+}
@@ -0,0 +1,798 @@
+//! Read existing missions, e.g. made with the game's mission editor, back into
+//! options that can be edited and generated again.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use configparser::ini::Ini;
+use thiserror::Error;
+
+use super::settings::parse_bool;
+use super::{
+    FormationLayout, FormationOption, MissionOptions, PlacementStrategy, RouteStyle, StoresOption,
+    TaskforceOptions, UnitOption, UnitSettings, VariantOption, WeaponState, BLUE, DEFAULT_VARIANT,
+    NEUTRAL, RED,
+};
+use crate::unit_db::{self as db, UnitDb, UnitType};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("failed to parse mission: {0}")]
+    Parse(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A key of an imported mission that isn't represented in the options.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownKey {
+    pub section: String,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// A unit of an imported mission, along with everything about it that the
+/// options of other units don't have, see `UnitOption::Imported`.
+#[derive(Clone, Debug)]
+pub struct ImportedUnit {
+    /// the unit's `Type`
+    pub id: String,
+    pub utype: UnitType,
+    /// `None` if the unit isn't in the unit database, e.g. from a mod that
+    /// isn't installed, it is kept in the mission regardless
+    pub unit: Option<db::Unit>,
+    pub heading: u16,
+    pub position: (f32, f32),
+    pub waypoints: Vec<(f32, f32)>,
+    /// keys of the unit's section that aren't understood, they are written
+    /// back out unchanged, wherever the unit ends up
+    pub unknown_keys: Vec<(String, Option<String>)>,
+}
+
+/// Something in an imported mission that couldn't be imported as is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportProblem {
+    /// the key isn't understood, it is kept and written back out unchanged
+    UnknownKey { section: String, key: String },
+    /// the value couldn't be parsed, it is kept and written back out
+    /// unchanged
+    InvalidValue {
+        section: String,
+        key: String,
+        value: String,
+    },
+    /// the key belongs to a unit or formation that isn't in the mission,
+    /// e.g. past its `NumberOf*` count, so it is dropped
+    Orphaned { section: String, key: String },
+    /// a unit section counted by `NumberOf*` doesn't exist
+    MissingSection(String),
+    /// a required key, e.g. a unit's `Type`, doesn't exist
+    MissingKey { section: String, key: String },
+    /// the unit isn't in the unit database, it is kept in the mission but
+    /// only its settings can be edited
+    UnknownUnit { section: String, id: String },
+    /// a formation refers to a unit section that wasn't imported
+    UnknownFormationMember { formation: String, member: String },
+}
+
+impl std::fmt::Display for ImportProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownKey { section, key } => write!(f, "unknown key {section}.{key}"),
+            Self::InvalidValue {
+                section,
+                key,
+                value,
+            } => write!(f, "invalid value `{value}` for {section}.{key}"),
+            Self::Orphaned { section, key } => {
+                write!(f, "{section}.{key} doesn't belong to any unit or formation")
+            }
+            Self::MissingSection(section) => write!(f, "missing section [{section}]"),
+            Self::MissingKey { section, key } => write!(f, "missing key {section}.{key}"),
+            Self::UnknownUnit { section, id } => write!(f, "unknown unit `{id}` in [{section}]"),
+            Self::UnknownFormationMember { formation, member } => {
+                write!(f, "unknown unit `{member}` in {formation}")
+            }
+        }
+    }
+}
+
+/// Everything that couldn't be imported as is, none of which is fatal.
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub problems: Vec<ImportProblem>,
+}
+
+impl ImportReport {
+    fn push(&mut self, problem: ImportProblem) {
+        self.problems.push(problem);
+    }
+}
+
+impl MissionOptions {
+    /// Load the mission ini at `path`, see `MissionOptions::from_ini`.
+    pub fn load(unit_db: &UnitDb, path: &Path) -> Result<(Self, ImportReport), ImportError> {
+        let mut config = Ini::new_cs();
+        config
+            .read(fs::read_to_string(path)?)
+            .map_err(ImportError::Parse)?;
+        Ok(Self::from_ini(unit_db, &config))
+    }
+
+    /// Import a mission, every unit keeps its position, heading and
+    /// waypoints when it is generated again.
+    ///
+    /// Anything that isn't understood is reported and kept in the options
+    /// unchanged, so that writing the mission back out loses nothing. Expects
+    /// `config` to have been loaded case sensitively.
+    pub fn from_ini(unit_db: &UnitDb, config: &Ini) -> (Self, ImportReport) {
+        let mut importer = Importer::new(config);
+        let defaults = MissionOptions::default();
+
+        let mut general = defaults.general;
+        let lat = importer.parse("Environment", "MapCenterLatitude");
+        let lon = importer.parse("Environment", "MapCenterLongitude");
+        if let Some(latlon) = lat.zip(lon) {
+            general.latlon = latlon;
+        }
+        if let Some(date) = importer.parse("Environment", "Date") {
+            general.date = date;
+        }
+        let mut environment = defaults.environment;
+        if let Some(time) = importer.parse("Environment", "Time") {
+            environment.time = Some(time);
+        }
+        if let Some(sea_state) = importer.parse("Environment", "SeaState") {
+            environment.sea_state = Some(sea_state);
+        }
+        if let Some(clouds) = importer.parse("Environment", "Clouds") {
+            environment.clouds = Some(clouds);
+        }
+        if let Some(wind_direction) = importer.parse("Environment", "WindDirection") {
+            environment.wind_direction = Some(wind_direction);
+        }
+
+        let neutral = importer.taskforce(unit_db, NEUTRAL, defaults.neutral.weapon_state);
+        let blue = importer.taskforce(unit_db, BLUE, defaults.blue.weapon_state);
+        let red = importer.taskforce(unit_db, RED, defaults.red.weapon_state);
+
+        let (unknown_keys, report) = importer.finish();
+        let options = MissionOptions {
+            general,
+            environment,
+            neutral,
+            blue,
+            red,
+            seed: None,
+            unknown_keys,
+        };
+        (options, report)
+    }
+}
+
+/// Keeps track of which keys have been imported, everything else is unknown.
+struct Importer<'a> {
+    config: &'a Ini,
+    used: HashSet<(String, String)>,
+    /// invalid values, kept as they were
+    invalid: Vec<UnknownKey>,
+    report: ImportReport,
+}
+
+impl<'a> Importer<'a> {
+    fn new(config: &'a Ini) -> Self {
+        Self {
+            config,
+            used: HashSet::new(),
+            invalid: Vec::new(),
+            report: ImportReport::default(),
+        }
+    }
+
+    fn use_key(&mut self, section: &str, key: &str) {
+        self.used.insert((section.to_owned(), key.to_owned()));
+    }
+
+    fn get(&mut self, section: &str, key: &str) -> Option<String> {
+        let value = self.config.get(section, key)?;
+        self.use_key(section, key);
+        Some(value)
+    }
+
+    /// Parse a value with `parse`, an invalid value is reported and kept.
+    fn parse_with<T>(
+        &mut self,
+        section: &str,
+        key: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<T> {
+        let value = self.get(section, key)?;
+        let parsed = parse(value.trim());
+        if parsed.is_none() {
+            self.invalid(section, key, value);
+        }
+        parsed
+    }
+
+    /// Report an invalid value, it is written back out as it was.
+    fn invalid(&mut self, section: &str, key: &str, value: String) {
+        self.report.push(ImportProblem::InvalidValue {
+            section: section.to_owned(),
+            key: key.to_owned(),
+            value: value.clone(),
+        });
+        self.invalid.push(UnknownKey {
+            section: section.to_owned(),
+            key: key.to_owned(),
+            value: Some(value),
+        });
+    }
+
+    fn parse<T: FromStr>(&mut self, section: &str, key: &str) -> Option<T> {
+        self.parse_with(section, key, |value| value.parse().ok())
+    }
+
+    fn taskforce(
+        &mut self,
+        unit_db: &UnitDb,
+        name: &str,
+        default_weapon_state: WeaponState,
+    ) -> TaskforceOptions {
+        // by the section they were imported from, in the order they were
+        let mut imported: Vec<(String, UnitOption)> = Vec::new();
+        let mut weapon_state = None;
+        for utype in UnitType::all() {
+            let key = format!("NumberOf{name}{}", utype.calitalised_plural());
+            let count: usize = self.parse("Mission", &key).unwrap_or(0);
+            for n in 1..=count {
+                let section = format!("{name}{}{n}", utype.capitalised_singular());
+                if let Some(option) = self.unit(unit_db, &section, utype, &mut weapon_state) {
+                    imported.push((section, option));
+                }
+            }
+        }
+        let by_section: HashMap<&str, &UnitOption> = imported
+            .iter()
+            .map(|(section, option)| (section.as_str(), option))
+            .collect();
+
+        let mut formations = Vec::new();
+        let mut in_formation = HashSet::new();
+        let count: usize = self
+            .parse("Mission", &format!("{name}_NumberOfFormations"))
+            .unwrap_or(0);
+        for n in 1..=count {
+            let key = format!("{name}_Formation{n}");
+            let Some(value) = self.get("Mission", &key) else {
+                self.report.push(ImportProblem::MissingKey {
+                    section: "Mission".into(),
+                    key,
+                });
+                continue;
+            };
+            let (members, layout) = match value.split_once('|') {
+                Some((members, layout)) => (members, layout.parse().ok()),
+                None => (value.as_str(), Some(FormationLayout::default())),
+            };
+            // the members are still imported, with the default layout
+            let layout = layout.unwrap_or_else(|| {
+                self.report.push(ImportProblem::InvalidValue {
+                    section: "Mission".into(),
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+                FormationLayout::default()
+            });
+            // the members are kept in their order, led by the first if it
+            // was imported, otherwise by the largest
+            let mut formation = FormationOption {
+                layout,
+                leader: None,
+                ..FormationOption::new(Vec::new())
+            };
+            let members = members.split(',').map(str::trim).filter(|m| !m.is_empty());
+            for (idx, member) in members.enumerate() {
+                let Some(option) = by_section.get(member) else {
+                    self.report.push(ImportProblem::UnknownFormationMember {
+                        formation: key.clone(),
+                        member: member.to_owned(),
+                    });
+                    continue;
+                };
+                // a unit can only be in a single formation, once
+                if in_formation.insert(member.to_owned()) {
+                    if idx == 0 {
+                        formation.leader = Some(0);
+                    }
+                    formation.units.push((*option).clone());
+                }
+            }
+            formations.push(formation);
+        }
+
+        let lone_units = imported
+            .iter()
+            .filter(|(section, _)| !in_formation.contains(section))
+            .map(|(_, option)| option.clone())
+            .collect();
+        TaskforceOptions {
+            weapon_state: weapon_state.unwrap_or(default_weapon_state),
+            // every unit has its own, anything missing is left to the game
+            settings: UnitSettings::default(),
+            units: lone_units,
+            // imported units keep their waypoints
+            route: RouteStyle::None,
+            placement: PlacementStrategy::Uniform,
+            formations,
+        }
+    }
+
+    /// Import the unit in `section`, units that aren't in the unit database
+    /// are imported too, so that they aren't lost.
+    ///
+    /// The weapon state is per taskforce, so only units matching the first
+    /// `weapon_state` have it imported.
+    fn unit(
+        &mut self,
+        unit_db: &UnitDb,
+        section: &str,
+        utype: UnitType,
+        weapon_state: &mut Option<WeaponState>,
+    ) -> Option<UnitOption> {
+        if !self.config.get_map_ref().contains_key(section) {
+            self.report
+                .push(ImportProblem::MissingSection(section.to_owned()));
+            return None;
+        }
+        let Some(id) = self.get(section, "Type") else {
+            self.report.push(ImportProblem::MissingKey {
+                section: section.to_owned(),
+                key: "Type".into(),
+            });
+            return None;
+        };
+
+        let variant = self
+            .get(section, "VariantReference")
+            .unwrap_or_else(|| DEFAULT_VARIANT.to_owned());
+        let stores = self
+            .parse_with(section, "Stores", |stores| match stores {
+                "Full" => Some("Full"),
+                "Depleted" => Some("Depleted"),
+                _ => None,
+            })
+            // the game's default
+            .unwrap_or("Depleted");
+        let loadout = self.get(section, "Loadout");
+        let heading = self.parse(section, "Heading").unwrap_or(0);
+        let position = self
+            .parse_with(section, "RelativePositionInNM", parse_position)
+            .unwrap_or((0.0, 0.0));
+        if let Some(state) = self.parse(section, "WeaponStatus") {
+            if *weapon_state.get_or_insert(state) != state {
+                // keep it as an unknown key of the unit instead
+                self.used
+                    .remove(&(section.to_owned(), "WeaponStatus".to_owned()));
+            }
+        }
+        let waypoint_count: usize = self.parse(section, "NumberOfWaypoints").unwrap_or(0);
+        let waypoints = (1..=waypoint_count)
+            .filter_map(|n| self.parse_with(section, &format!("Waypoint{n}"), parse_position))
+            .collect();
+        let settings = UnitSettings {
+            telegraph: self.parse(section, "Telegraph"),
+            crew_skill: self.parse(section, "CrewSkill"),
+            radars_active: self.parse_with(section, "RadarsActive", parse_bool),
+            active_sonars_enabled: self.parse_with(section, "ActiveSonarsEnabled", parse_bool),
+            towed_array_deployed: self.parse_with(section, "TowedArrayDeployed", parse_bool),
+            towed_decoy_deployed: self.parse_with(section, "TowedDecoyDeployed", parse_bool),
+            damage: self.parse(section, "Damage"),
+            mission_type: self.parse(section, "MissionType"),
+            station_role: self.parse(section, "StationRole"),
+        };
+
+        let unit = unit_db.by_id(&id).cloned();
+        if unit.is_none() {
+            self.report.push(ImportProblem::UnknownUnit {
+                section: section.to_owned(),
+                id: id.clone(),
+            });
+        }
+        let imported = ImportedUnit {
+            id,
+            utype,
+            unit,
+            heading,
+            position,
+            waypoints,
+            unknown_keys: self.unknown_keys_of(section),
+        };
+        Some(UnitOption::Imported {
+            unit: Box::new(imported),
+            variant: match variant.as_str() {
+                DEFAULT_VARIANT => VariantOption::Default,
+                variant => VariantOption::Variant(variant.to_owned()),
+            },
+            stores: match (loadout, stores) {
+                (Some(loadout), _) => StoresOption::Loadout(loadout),
+                (None, "Depleted") => StoresOption::Depleted,
+                (None, _) => StoresOption::Full,
+            },
+            settings,
+        })
+    }
+
+    /// Every key of a unit `section` that wasn't imported, including invalid
+    /// values, as (key, value).
+    fn unknown_keys_of(&mut self, section: &str) -> Vec<(String, Option<String>)> {
+        let mut unknown_keys = Vec::new();
+        let keys = self.config.get_map_ref().get(section).into_iter().flatten();
+        for (key, value) in keys {
+            if !self.used.insert((section.to_owned(), key.clone())) {
+                continue;
+            }
+            self.report.push(ImportProblem::UnknownKey {
+                section: section.to_owned(),
+                key: key.clone(),
+            });
+            unknown_keys.push((key.clone(), value.clone()));
+        }
+        let (invalid, rest) = self
+            .invalid
+            .drain(..)
+            .partition(|unknown| unknown.section == section);
+        self.invalid = rest;
+        unknown_keys.extend(
+            invalid
+                .into_iter()
+                .map(|unknown| (unknown.key, unknown.value)),
+        );
+        unknown_keys
+    }
+
+    /// Every key that wasn't imported, in the order they appear, along with
+    /// the report.
+    ///
+    /// Keys of units or formations that weren't imported are dropped, since
+    /// they'd end up on whichever unit or formation takes their place.
+    fn finish(mut self) -> (Vec<UnknownKey>, ImportReport) {
+        let mut unknown_keys = Vec::new();
+        for (section, keys) in self.config.get_map_ref() {
+            for (key, value) in keys {
+                if self.used.contains(&(section.clone(), key.clone())) {
+                    continue;
+                }
+                if is_orphaned(section, key) {
+                    self.report.push(ImportProblem::Orphaned {
+                        section: section.clone(),
+                        key: key.clone(),
+                    });
+                    continue;
+                }
+                self.report.push(ImportProblem::UnknownKey {
+                    section: section.clone(),
+                    key: key.clone(),
+                });
+                unknown_keys.push(UnknownKey {
+                    section: section.clone(),
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        unknown_keys.append(&mut self.invalid);
+        (unknown_keys, self.report)
+    }
+}
+
+/// Whether a key that wasn't imported belongs to a unit or formation, e.g.
+/// `[Taskforce1Vessel3]` or `Mission.Taskforce1_Formation2`.
+fn is_orphaned(section: &str, key: &str) -> bool {
+    // e.g. Taskforce1Vessel followed by a number
+    let is_numbered = |name: &str, prefix: &str| {
+        name.strip_prefix(prefix)
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    };
+    [NEUTRAL, BLUE, RED].into_iter().any(|taskforce| {
+        let is_unit = UnitType::all().into_iter().any(|utype| {
+            let prefix = format!("{taskforce}{}", utype.capitalised_singular());
+            is_numbered(section, &prefix)
+        });
+        let is_formation =
+            section == "Mission" && is_numbered(key, &format!("{taskforce}_Formation"));
+        is_unit || is_formation
+    })
+}
+
+/// Parse a `RelativePositionInNM` of `x,altitude,y` into `(x, y)`.
+fn parse_position(value: &str) -> Option<(f32, f32)> {
+    let parts: Vec<f32> = value
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [x, _, y] => Some((x, y)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::Mission;
+    use crate::terrain::Terrain;
+
+    const SINGLE_VESSEL: &str = include_str!("../../samples/Single Vessel.ini");
+    const FORMATION: &str = include_str!("../../samples/Formation.ini");
+
+    fn import(sample: &str) -> (Ini, MissionOptions, ImportReport) {
+        let mut config = Ini::new_cs();
+        config.read(sample.into()).unwrap();
+        let (options, report) = MissionOptions::from_ini(&UnitDb::fixture(), &config);
+        (config, options, report)
+    }
+
+    /// Generate the mission again, like the app does.
+    fn generate(options: MissionOptions) -> Ini {
+        let mut config = Ini::new_cs();
+        Mission::new(&UnitDb::fixture(), &Terrain::default(), options).write_ini(&mut config);
+        config
+    }
+
+    fn assert_round_trips(sample: &str) {
+        let (config, options, _) = import(sample);
+        let written = generate(options);
+        for (section, keys) in config.get_map_ref() {
+            for (key, value) in keys {
+                assert_eq!(written.get(section, key), *value, "{section}.{key}");
+            }
+        }
+    }
+
+    fn imported(option: &UnitOption) -> &ImportedUnit {
+        match option {
+            UnitOption::Imported { unit, .. } => unit,
+            _ => panic!("not imported: {option:?}"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_round_trips(SINGLE_VESSEL);
+        assert_round_trips(FORMATION);
+    }
+
+    #[test]
+    fn test_import_single_vessel() {
+        let (_, options, report) = import(SINGLE_VESSEL);
+        assert_eq!(options.general.latlon, (54.27, -26.28));
+        assert_eq!(options.general.date.to_string(), "1985,6,26");
+        assert_eq!(options.blue.weapon_state, WeaponState::Tight);
+        assert!(matches!(
+            &options.blue.units[..],
+            [UnitOption::Imported { unit, variant: VariantOption::Default, stores: StoresOption::Full, settings }]
+                if unit.unit.as_ref().is_some_and(|u| u.id == "wp_bpk_kashin")
+                    && unit.heading == 90
+                    && settings.telegraph == Some(2)
+        ));
+        // the merchant isn't in the fixture, but is still kept
+        let merchant = imported(&options.neutral.units[0]);
+        assert_eq!(merchant.id, "civ_ms_act_1");
+        assert!(merchant.unit.is_none());
+        assert_eq!(merchant.position, (9.93, 0.03));
+        assert!(report.problems.contains(&ImportProblem::UnknownUnit {
+            section: "NeutralVessel1".into(),
+            id: "civ_ms_act_1".into(),
+        }));
+        assert!(report.problems.contains(&ImportProblem::UnknownKey {
+            section: "Environment".into(),
+            key: "ConvertTimeToLocal".into(),
+        }));
+    }
+
+    #[test]
+    fn test_import_formation() {
+        let (_, options, report) = import(FORMATION);
+        let blue = &options.blue;
+        assert!(blue.units.is_empty());
+        assert_eq!(blue.formations.len(), 1);
+        assert_eq!(blue.formations[0].units.len(), 3);
+        assert_eq!(blue.formations[0].leader, Some(0));
+        assert_eq!(
+            blue.formations[0].layout.to_string(),
+            "Group Name 1|Circle|1.5|OverrideSpawnPositions"
+        );
+        assert!(!report
+            .problems
+            .iter()
+            .any(|p| matches!(p, ImportProblem::UnknownFormationMember { .. })));
+    }
+
+    #[test]
+    fn test_import_station_roles() {
+        let (_, options, _) = import(
+            "[Mission]
+NumberOfTaskforce1Vessels=3
+Taskforce1_NumberOfFormations=1
+Taskforce1_Formation1=Taskforce1Vessel2,Taskforce1Vessel3|Group|Circle|2|OverrideSpawnPositions
+[Taskforce1Vessel1]
+Type=wp_bpk_kashin
+StationRole=ASW
+[Taskforce1Vessel2]
+Type=usn_dd_spruance
+StationRole=ASuW
+[Taskforce1Vessel3]
+Type=wp_bpk_kashin",
+        );
+        // lone units and leaders keep theirs, even though they aren't escorts
+        let written = generate(options);
+        assert_eq!(
+            written.get("Taskforce1Vessel1", "StationRole").as_deref(),
+            Some("ASW")
+        );
+        assert!(written
+            .get("Mission", "Taskforce1_Formation1")
+            .is_some_and(|f| f.starts_with("Taskforce1Vessel2,Taskforce1Vessel3|")));
+        assert_eq!(
+            written.get("Taskforce1Vessel2", "StationRole").as_deref(),
+            Some("ASuW")
+        );
+    }
+
+    #[test]
+    fn test_import_problems() {
+        let (_, options, report) = import(
+            "[Mission]
+NumberOfTaskforce2Vessels=2
+Taskforce2_NumberOfFormations=1
+Taskforce2_Formation1=Taskforce2Vessel3,Taskforce2Vessel1|Group|Line|2|OverrideSpawnPositions
+Taskforce2_Formation2=Taskforce2Vessel1|Group|Line|2|OverrideSpawnPositions
+[Taskforce2Vessel1]
+Type=wp_bpk_kashin
+Heading=north
+[Taskforce2Vessel3]
+Type=wp_bpk_kashin",
+        );
+        assert_eq!(
+            report.problems,
+            vec![
+                ImportProblem::InvalidValue {
+                    section: "Taskforce2Vessel1".into(),
+                    key: "Heading".into(),
+                    value: "north".into(),
+                },
+                ImportProblem::MissingSection("Taskforce2Vessel2".into()),
+                ImportProblem::UnknownFormationMember {
+                    formation: "Taskforce2_Formation1".into(),
+                    member: "Taskforce2Vessel3".into(),
+                },
+                ImportProblem::Orphaned {
+                    section: "Mission".into(),
+                    key: "Taskforce2_Formation2".into(),
+                },
+                ImportProblem::Orphaned {
+                    section: "Taskforce2Vessel3".into(),
+                    key: "Type".into(),
+                },
+            ]
+        );
+        // without its first member the formation is led by the largest
+        assert_eq!(options.red.formations[0].leader, None);
+        // the invalid heading is kept as is, with its unit
+        let unit = imported(&options.red.formations[0].units[0]);
+        assert_eq!(
+            unit.unknown_keys,
+            vec![("Heading".into(), Some("north".into()))]
+        );
+        assert!(options.unknown_keys.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_follow_units() {
+        let (_, mut options, _) = import(
+            "[Debug]
+DisableEnemyAIPlayer=True
+[Mission]
+NumberOfTaskforce1Vessels=2
+[Taskforce1Vessel1]
+Type=wp_bpk_kashin
+WeaponStatus=Tight
+[Taskforce1Vessel2]
+Type=usn_dd_spruance
+WeaponStatus=Free
+Mystery=42",
+        );
+        // the taskforce takes the first unit's weapon status, the spruance
+        // keeps its own
+        let unit = imported(&options.blue.units[1]);
+        assert_eq!(
+            unit.unknown_keys,
+            vec![
+                ("WeaponStatus".into(), Some("Free".into())),
+                ("Mystery".into(), Some("42".into())),
+            ]
+        );
+
+        // the spruance is now the first vessel, and takes its keys with it
+        options.blue.units.remove(0);
+        let written = generate(options);
+        assert_eq!(
+            written.get("Taskforce1Vessel1", "Type").as_deref(),
+            Some("usn_dd_spruance")
+        );
+        assert_eq!(
+            written.get("Taskforce1Vessel1", "WeaponStatus").as_deref(),
+            Some("Free")
+        );
+        assert_eq!(
+            written.get("Taskforce1Vessel1", "Mystery").as_deref(),
+            Some("42")
+        );
+        assert!(!written.sections().contains(&"Taskforce1Vessel2".to_owned()));
+        // global keys are always kept
+        assert_eq!(
+            written.get("Debug", "DisableEnemyAIPlayer").as_deref(),
+            Some("True")
+        );
+    }
+
+    #[test]
+    fn test_import_invalid_layout() {
+        let value = "Taskforce1Vessel1|Group|Circle|wide|OverrideSpawnPositions";
+        let (_, options, report) = import(&format!(
+            "[Mission]
+NumberOfTaskforce1Vessels=1
+Taskforce1_NumberOfFormations=1
+Taskforce1_Formation1={value}
+[Taskforce1Vessel1]
+Type=wp_bpk_kashin"
+        ));
+        let blue = &options.blue;
+        assert_eq!(blue.formations[0].units.len(), 1);
+        assert_eq!(blue.formations[0].layout, FormationLayout::default());
+        assert_eq!(
+            report.problems,
+            vec![ImportProblem::InvalidValue {
+                section: "Mission".into(),
+                key: "Taskforce1_Formation1".into(),
+                value: value.into(),
+            }]
+        );
+        // the formation is generated again, so the value isn't kept
+        assert!(options.unknown_keys.is_empty());
+    }
+
+    #[test]
+    fn test_import_waypoints() {
+        // only round trips the keys we write, there's no game saved sample
+        // with waypoints to check them against
+        let sample = "[Mission]
+NumberOfTaskforce1Vessels=1
+[Taskforce1Vessel1]
+Type=usn_dd_spruance
+NumberOfWaypoints=2
+Waypoint1=10,0,5
+Waypoint2=-3.5,0,2";
+        let (config, options, report) = import(sample);
+        assert_eq!(
+            imported(&options.blue.units[0]).waypoints,
+            vec![(10.0, 5.0), (-3.5, 2.0)]
+        );
+        assert!(report.problems.is_empty());
+        let written = generate(options);
+        assert_eq!(
+            written.get("Taskforce1Vessel1", "Waypoint2"),
+            config.get("Taskforce1Vessel1", "Waypoint2")
+        );
+    }
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("9.93,0,0.03"), Some((9.93, 0.03)));
+        assert_eq!(parse_position("1,2"), None);
+        assert_eq!(parse_position("a,0,1"), None);
+    }
+}
@@ -0,0 +1,31 @@
+//! Parsing option values, e.g. those read from an imported mission.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("invalid {kind} `{value}`")]
+pub struct InvalidValueError {
+    kind: &'static str,
+    value: String,
+}
+
+impl InvalidValueError {
+    pub fn new(kind: &'static str, value: &str) -> Self {
+        Self {
+            kind,
+            value: value.to_owned(),
+        }
+    }
+}
+
+/// Find the value displayed as `s`, ignoring case.
+pub fn parse_named<T: ToString, const N: usize>(
+    kind: &'static str,
+    values: [T; N],
+    s: &str,
+) -> Result<T, InvalidValueError> {
+    values
+        .into_iter()
+        .find(|value| value.to_string().eq_ignore_ascii_case(s.trim()))
+        .ok_or_else(|| InvalidValueError::new(kind, s))
+}
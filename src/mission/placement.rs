@@ -0,0 +1,419 @@
+//! Where units start the mission, keeping opposing taskforces apart.
+
+use rand::Rng;
+
+use super::route;
+use super::{GeneralOptions, Problem};
+use crate::rand_ext::{self, MissionRng};
+use crate::terrain::{self, Terrain};
+use crate::unit_db::{self as db, UnitType};
+
+/// How many random positions to try before settling for the one closest to
+/// meeting the separation options.
+const ATTEMPTS: usize = 100;
+/// how far from the group centre clustered units are placed, in nm
+const CLUSTER_RADIUS: f32 = 10.0;
+/// the shallowest water submarines are placed in, in metres
+const MIN_SUBMARINE_DEPTH: f32 = 50.0;
+
+/// How the units of a taskforce are spread over the mission area.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PlacementStrategy {
+    /// anywhere in the mission area
+    #[default]
+    Uniform,
+    /// around a random group centre
+    Clustered,
+    /// in the taskforce's own half of the mission area, blue and red are on
+    /// opposite sides while neutrals can be anywhere
+    OpposingSectors,
+}
+
+impl PlacementStrategy {
+    pub fn all() -> [Self; 3] {
+        use PlacementStrategy::*;
+        [Uniform, Clustered, OpposingSectors]
+    }
+}
+
+impl std::fmt::Display for PlacementStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use PlacementStrategy::*;
+        let str = match self {
+            Uniform => "Uniform",
+            Clustered => "Clustered",
+            OpposingSectors => "Opposing Sectors",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// How far apart units are placed in nm, any that are `None` aren't
+/// enforced.
+///
+/// These are met where possible, a crowded mission area may not allow it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SeparationOptions {
+    /// between any two units
+    pub min_spacing: Option<f32>,
+    /// between any blue and red unit
+    pub min_enemy_range: Option<f32>,
+    /// from red units to the nearest blue unit, and vice versa for any blue
+    /// units placed after red
+    pub max_enemy_range: Option<f32>,
+}
+
+/// Which taskforce a unit belongs to, the enemy of blue is red and the other
+/// way round.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum Side {
+    Neutral,
+    Blue,
+    Red,
+}
+
+impl Side {
+    /// The name of the taskforce, as used by `Problem`.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Neutral => "Neutral",
+            Self::Blue => "Blue",
+            Self::Red => "Red",
+        }
+    }
+
+    fn is_enemy(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::Blue, Self::Red) | (Self::Red, Self::Blue)
+        )
+    }
+}
+
+/// Places the units of every taskforce, remembering where each went.
+pub(super) struct Placer<'a> {
+    general: &'a GeneralOptions,
+    terrain: &'a Terrain,
+    /// the bearing from the map centre to the middle of blue's sector, red's
+    /// is opposite
+    sector_bearing: f32,
+    placed: Vec<(Side, (f32, f32))>,
+    /// units that could only be placed somewhere unsuitable
+    pub problems: Vec<Problem>,
+}
+
+impl<'a> Placer<'a> {
+    pub fn new(general: &'a GeneralOptions, terrain: &'a Terrain, rng: &mut MissionRng) -> Self {
+        Self {
+            general,
+            terrain,
+            sector_bearing: rand_ext::heading(rng) as f32,
+            placed: Vec::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    /// Start placing the units of a taskforce.
+    pub fn taskforce<'p>(
+        &'p mut self,
+        side: Side,
+        strategy: PlacementStrategy,
+        rng: &mut MissionRng,
+    ) -> TaskforcePlacer<'p, 'a> {
+        let size = self.general.size;
+        let centre = match strategy {
+            PlacementStrategy::Clustered => self.best_of(side, UnitType::Vessel, rng, |rng| {
+                Some(rand_ext::position(rng, &size))
+            }),
+            _ => None,
+        };
+        TaskforcePlacer {
+            placer: self,
+            side,
+            strategy,
+            centre: centre.unwrap_or((0.0, 0.0)),
+        }
+    }
+
+    /// The `candidate` position that best meets the separation options,
+    /// `None` if there are no candidates.
+    ///
+    /// Positions unsuitable for the unit type, e.g. a ship on land, are only
+    /// used if every candidate is.
+    fn best_of<F>(
+        &self,
+        side: Side,
+        utype: UnitType,
+        rng: &mut MissionRng,
+        mut candidate: F,
+    ) -> Option<(f32, f32)>
+    where
+        F: FnMut(&mut MissionRng) -> Option<(f32, f32)>,
+    {
+        let mut best: Option<(f32, (f32, f32))> = None;
+        for _ in 0..ATTEMPTS {
+            let point = candidate(rng)?;
+            let shortfall = match self.is_suitable(utype, point) {
+                true => self.shortfall(side, point),
+                false => f32::INFINITY,
+            };
+            if shortfall == 0.0 {
+                return Some(point);
+            }
+            if best.is_none_or(|(best, _)| shortfall < best) {
+                best = Some((shortfall, point));
+            }
+        }
+        best.map(|(_, point)| point)
+    }
+
+    /// Whether a unit of the given type can be at `point`, anywhere is
+    /// suitable without terrain data.
+    fn is_suitable(&self, utype: UnitType, point: (f32, f32)) -> bool {
+        let latlon = terrain::offset_latlon(self.general.latlon, point);
+        let Some(elevation) = self.terrain.elevation(latlon) else {
+            return true;
+        };
+        match utype {
+            UnitType::Vessel => elevation < 0.0,
+            UnitType::Submarine => elevation <= -MIN_SUBMARINE_DEPTH,
+            // aircraft are airborne, and land units are in the land area
+            _ => true,
+        }
+    }
+
+    /// How many nm `point` is from meeting every separation option, 0 if it
+    /// meets them all.
+    fn shortfall(&self, side: Side, point: (f32, f32)) -> f32 {
+        let separation = &self.general.separation;
+        let mut shortfall = 0.0;
+        let mut nearest_enemy: Option<f32> = None;
+        for (other_side, other) in &self.placed {
+            let distance = (point.0 - other.0).hypot(point.1 - other.1);
+            if let Some(min) = separation.min_spacing {
+                shortfall += (min - distance).max(0.0);
+            }
+            if side.is_enemy(*other_side) {
+                if let Some(min) = separation.min_enemy_range {
+                    shortfall += (min - distance).max(0.0);
+                }
+                nearest_enemy = Some(nearest_enemy.map_or(distance, |d| d.min(distance)));
+            }
+        }
+        if let Some((max, nearest)) = separation.max_enemy_range.zip(nearest_enemy) {
+            shortfall += (nearest - max).max(0.0);
+        }
+        shortfall
+    }
+}
+
+/// Places the units of a single taskforce, see `Placer::taskforce`.
+pub(super) struct TaskforcePlacer<'p, 'a> {
+    placer: &'p mut Placer<'a>,
+    side: Side,
+    strategy: PlacementStrategy,
+    /// the group centre of clustered units
+    centre: (f32, f32),
+}
+
+impl TaskforcePlacer<'_, '_> {
+    /// A position for a unit of the given type, land units are placed within
+    /// the land area, `None` if there isn't one.
+    pub fn position(&mut self, utype: UnitType, rng: &mut MissionRng) -> Option<(f32, f32)> {
+        let placer = &*self.placer;
+        let general = placer.general;
+        let position = match utype {
+            UnitType::LandUnit => {
+                let land = general.land.as_ref()?;
+                placer.best_of(self.side, utype, rng, |rng| Some(land.position(rng)))
+            }
+            _ => placer.best_of(self.side, utype, rng, |rng| Some(self.candidate(rng))),
+        }?;
+        self.placer.placed.push((self.side, position));
+        Some(position)
+    }
+
+    /// A position for `unit`, like `position`. If every position tried is
+    /// unsuitable for it, e.g. a ship on land, it is still placed but
+    /// reported in `Placer::problems`.
+    pub fn place(&mut self, unit: &db::Unit, rng: &mut MissionRng) -> Option<(f32, f32)> {
+        let position = self.position(unit.utype, rng)?;
+        if !self.placer.is_suitable(unit.utype, position) {
+            self.placer.problems.push(Problem::UnsuitablePosition {
+                taskforce: self.side.name(),
+                unit: unit.id.clone(),
+            });
+        }
+        Some(position)
+    }
+
+    /// Remember a unit that is already placed, e.g. imported, so that the
+    /// rest are kept apart from it.
+    pub fn fixed(&mut self, position: (f32, f32)) {
+        self.placer.placed.push((self.side, position));
+    }
+
+    /// A random sea position following the placement strategy.
+    fn candidate(&self, rng: &mut MissionRng) -> (f32, f32) {
+        let size = &self.placer.general.size;
+        match (self.strategy, self.side) {
+            (PlacementStrategy::Clustered, _) => {
+                let distance = CLUSTER_RADIUS * rng.gen::<f32>().sqrt();
+                let bearing = rand_ext::heading(rng) as f32;
+                let point = route::offset(self.centre, bearing, distance);
+                route::clamp(point, size)
+            }
+            (PlacementStrategy::OpposingSectors, Side::Blue | Side::Red) => {
+                let (x, y) = rand_ext::position(rng, size);
+                let bearing = self.placer.sector_bearing.to_radians();
+                let towards_blue = x * bearing.sin() + y * bearing.cos();
+                let in_sector = match self.side {
+                    Side::Blue => towards_blue >= 0.0,
+                    _ => towards_blue <= 0.0,
+                };
+                // the area is symmetric, so mirroring keeps the point in it
+                match in_sector {
+                    true => (x, y),
+                    false => (-x, -y),
+                }
+            }
+            _ => rand_ext::position(rng, size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::MissionOptions;
+
+    fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+        (a.0 - b.0).hypot(a.1 - b.1)
+    }
+
+    #[test]
+    fn test_clustered() {
+        let general = MissionOptions::default().general;
+        let terrain = Terrain::default();
+        let rng = &mut rand_ext::seeded(0);
+        let mut placer = Placer::new(&general, &terrain, rng);
+        let mut taskforce = placer.taskforce(Side::Blue, PlacementStrategy::Clustered, rng);
+        let centre = taskforce.centre;
+        for _ in 0..20 {
+            let position = taskforce.position(UnitType::Vessel, rng).unwrap();
+            assert!(distance(centre, position) <= CLUSTER_RADIUS + 0.001);
+        }
+        // no land area to place land units in
+        assert_eq!(taskforce.position(UnitType::LandUnit, rng), None);
+    }
+
+    #[test]
+    fn test_opposing_sectors() {
+        let general = MissionOptions::default().general;
+        let terrain = Terrain::default();
+        let rng = &mut rand_ext::seeded(0);
+        let mut placer = Placer::new(&general, &terrain, rng);
+        let bearing = placer.sector_bearing.to_radians();
+        let towards_blue = |(x, y): (f32, f32)| x * bearing.sin() + y * bearing.cos();
+        let strategy = PlacementStrategy::OpposingSectors;
+        let mut blue = placer.taskforce(Side::Blue, strategy, rng);
+        for _ in 0..20 {
+            let position = blue.position(UnitType::Vessel, rng).unwrap();
+            assert!(towards_blue(position) >= 0.0);
+        }
+        let mut red = placer.taskforce(Side::Red, strategy, rng);
+        for _ in 0..20 {
+            let position = red.position(UnitType::Vessel, rng).unwrap();
+            assert!(towards_blue(position) <= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_terrain() {
+        let general = MissionOptions::default().general;
+        let (lat, lon) = general.latlon;
+        // land to the west of the map centre, deep water to the east
+        let terrain: Terrain = format!(
+            "ncols 2\nnrows 1\nxllcorner {}\nyllcorner {}\ncellsize 5\n100 -3000",
+            lon - 5.0,
+            lat - 2.5
+        )
+        .parse()
+        .unwrap();
+        let rng = &mut rand_ext::seeded(0);
+        let mut placer = Placer::new(&general, &terrain, rng);
+        let mut blue = placer.taskforce(Side::Blue, PlacementStrategy::Uniform, rng);
+        for utype in [UnitType::Vessel, UnitType::Submarine] {
+            for _ in 0..20 {
+                let (x, _) = blue.position(utype, rng).unwrap();
+                assert!(x >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsuitable() {
+        let general = MissionOptions::default().general;
+        let (lat, lon) = general.latlon;
+        let land: Terrain = format!(
+            "ncols 1\nnrows 1\nxllcorner {}\nyllcorner {}\ncellsize 10\n100",
+            lon - 5.0,
+            lat - 5.0
+        )
+        .parse()
+        .unwrap();
+        let unit_db = crate::unit_db::UnitDb::fixture();
+        let rng = &mut rand_ext::seeded(0);
+        let mut placer = Placer::new(&general, &land, rng);
+        let mut blue = placer.taskforce(Side::Blue, PlacementStrategy::Uniform, rng);
+        let spruance = unit_db.by_id("usn_dd_spruance").unwrap();
+        assert!(blue.place(spruance, rng).is_some());
+        assert_eq!(
+            placer.problems,
+            vec![Problem::UnsuitablePosition {
+                taskforce: "Blue",
+                unit: "usn_dd_spruance".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_separation() {
+        let mut general = MissionOptions::default().general;
+        general.separation = SeparationOptions {
+            min_spacing: Some(5.0),
+            min_enemy_range: Some(40.0),
+            max_enemy_range: Some(80.0),
+        };
+        let terrain = Terrain::default();
+        let rng = &mut rand_ext::seeded(0);
+        let mut placer = Placer::new(&general, &terrain, rng);
+        let mut blue = placer.taskforce(Side::Blue, PlacementStrategy::Clustered, rng);
+        for _ in 0..5 {
+            blue.position(UnitType::Vessel, rng);
+        }
+        let mut red = placer.taskforce(Side::Red, PlacementStrategy::Uniform, rng);
+        for _ in 0..5 {
+            red.position(UnitType::Vessel, rng);
+        }
+
+        let placed = &placer.placed;
+        for (idx, (side, point)) in placed.iter().enumerate() {
+            for (other_side, other) in &placed[idx + 1..] {
+                let distance = distance(*point, *other);
+                assert!(distance >= 5.0);
+                if side.is_enemy(*other_side) {
+                    assert!(distance >= 40.0);
+                }
+            }
+            if *side == Side::Red {
+                let nearest_blue = placed
+                    .iter()
+                    .filter(|(side, _)| *side == Side::Blue)
+                    .map(|(_, blue)| distance(*point, *blue))
+                    .fold(f32::MAX, f32::min);
+                assert!(nearest_blue <= 80.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Waypoints for units to follow, so that they don't just steam in a straight
+//! line.
+//!
+//! None of the sample missions have waypoints, so the keys they are written
+//! as (`NumberOfWaypoints` and `WaypointN`) are unconfirmed and routes are
+//! off unless chosen.
+
+use crate::rand_ext::{self, MissionRng};
+
+// distances in nm
+const PATROL_BOX_SIZE: f32 = 20.0;
+const RACETRACK_LENGTH: f32 = 30.0;
+const RACETRACK_WIDTH: f32 = 5.0;
+const ZIG_ZAG_LEG: f32 = 10.0;
+const ZIG_ZAG_OFFSET: f32 = 3.0;
+
+/// The shape of the route a group of units follows.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RouteStyle {
+    /// no waypoints, the units keep their heading
+    #[default]
+    None,
+    /// around a box centred on the start position
+    PatrolBox,
+    /// back and forth along a random heading
+    Racetrack,
+    /// straight to a random point in the mission area
+    Transit,
+    /// to a random point in the mission area, weaving either side of the
+    /// direct route
+    ZigZag,
+}
+
+impl RouteStyle {
+    pub fn all() -> [Self; 5] {
+        use RouteStyle::*;
+        [None, PatrolBox, Racetrack, Transit, ZigZag]
+    }
+
+    /// The waypoints of a route from `start`, kept within the mission area
+    /// of the given `size`.
+    pub fn waypoints(
+        &self,
+        start: (f32, f32),
+        size: &(u16, u16),
+        rng: &mut MissionRng,
+    ) -> Vec<(f32, f32)> {
+        let (x, y) = start;
+        let waypoints = match self {
+            Self::None => vec![],
+            Self::PatrolBox => {
+                let half = PATROL_BOX_SIZE / 2.0;
+                vec![
+                    (x - half, y + half),
+                    (x + half, y + half),
+                    (x + half, y - half),
+                    (x - half, y - half),
+                ]
+            }
+            Self::Racetrack => {
+                let heading = rand_ext::heading(rng) as f32;
+                let ahead = offset(start, heading, RACETRACK_LENGTH);
+                let beside = |point| offset(point, heading + 90.0, RACETRACK_WIDTH);
+                vec![ahead, beside(ahead), beside(start), start]
+            }
+            Self::Transit => vec![rand_ext::position(rng, size)],
+            Self::ZigZag => {
+                let end = rand_ext::position(rng, size);
+                let heading = bearing(start, end) as f32;
+                let distance = (end.0 - x).hypot(end.1 - y);
+                let legs = (distance / ZIG_ZAG_LEG).ceil().max(1.0) as usize;
+                let mut waypoints: Vec<(f32, f32)> = (1..legs)
+                    .map(|leg| {
+                        let along = offset(start, heading, leg as f32 * ZIG_ZAG_LEG);
+                        let side = if leg % 2 == 0 { -90.0 } else { 90.0 };
+                        offset(along, heading + side, ZIG_ZAG_OFFSET)
+                    })
+                    .collect();
+                waypoints.push(end);
+                waypoints
+            }
+        };
+        waypoints
+            .into_iter()
+            .map(|point| clamp(point, size))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for RouteStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use RouteStyle::*;
+        let str = match self {
+            None => "None",
+            PatrolBox => "Patrol Box",
+            Racetrack => "Racetrack",
+            Transit => "Transit",
+            ZigZag => "Zig-Zag",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// The heading in degrees (0 is north) from one point to another.
+pub fn bearing(from: (f32, f32), to: (f32, f32)) -> u16 {
+    let degrees = (to.0 - from.0).atan2(to.1 - from.1).to_degrees();
+    (degrees.rem_euclid(360.0).round() as u16) % 360
+}
+
+/// Move `distance` nm from `point` along `heading`.
+pub(super) fn offset(point: (f32, f32), heading: f32, distance: f32) -> (f32, f32) {
+    let radians = heading.to_radians();
+    (
+        point.0 + distance * radians.sin(),
+        point.1 + distance * radians.cos(),
+    )
+}
+
+/// Keep `point` within the mission area.
+pub(super) fn clamp(point: (f32, f32), size: &(u16, u16)) -> (f32, f32) {
+    let half_w = size.0 as f32 / 2.0;
+    let half_h = size.1 as f32 / 2.0;
+    (
+        point.0.clamp(-half_w, half_w),
+        point.1.clamp(-half_h, half_h),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearing() {
+        assert_eq!(bearing((0.0, 0.0), (0.0, 10.0)), 0);
+        assert_eq!(bearing((0.0, 0.0), (10.0, 0.0)), 90);
+        assert_eq!(bearing((0.0, 0.0), (0.0, -10.0)), 180);
+        assert_eq!(bearing((0.0, 0.0), (-10.0, 0.0)), 270);
+    }
+
+    #[test]
+    fn test_waypoints() {
+        let rng = &mut rand_ext::seeded(0);
+        let size = (100, 100);
+        assert!(RouteStyle::None
+            .waypoints((0.0, 0.0), &size, rng)
+            .is_empty());
+
+        let patrol = RouteStyle::PatrolBox.waypoints((0.0, 0.0), &size, rng);
+        assert_eq!(
+            patrol,
+            vec![(-10.0, 10.0), (10.0, 10.0), (10.0, -10.0), (-10.0, -10.0)]
+        );
+        // kept within the area
+        let patrol = RouteStyle::PatrolBox.waypoints((50.0, 50.0), &size, rng);
+        assert_eq!(patrol[1], (50.0, 50.0));
+
+        let racetrack = RouteStyle::Racetrack.waypoints((0.0, 0.0), &size, rng);
+        assert_eq!(racetrack.len(), 4);
+        assert_eq!(racetrack[3], (0.0, 0.0));
+
+        for style in RouteStyle::all() {
+            let waypoints = style.waypoints((0.0, 0.0), &size, rng);
+            assert!(waypoints
+                .iter()
+                .all(|(x, y)| x.abs() <= 50.0 && y.abs() <= 50.0));
+        }
+    }
+}
@@ -0,0 +1,424 @@
+//! How a unit starts the mission, e.g. its speed and whether its radars are
+//! on.
+
+use std::str::FromStr;
+
+use super::parse::{parse_named, InvalidValueError};
+use crate::unit_db::{self as db, SizeClass, UnitType};
+use configparser::ini::Ini;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CrewSkill {
+    Green,
+    Trained,
+    Veteran,
+    Elite,
+}
+
+impl CrewSkill {
+    pub fn all() -> [Self; 4] {
+        use CrewSkill::*;
+        [Green, Trained, Veteran, Elite]
+    }
+}
+
+impl std::fmt::Display for CrewSkill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use CrewSkill::*;
+        let str = match self {
+            Green => "Green",
+            Trained => "Trained",
+            Veteran => "Veteran",
+            Elite => "Elite",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for CrewSkill {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("crew skill", Self::all(), s)
+    }
+}
+
+/// How damaged a unit starts the mission.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Damage {
+    None,
+    Light,
+    Moderate,
+    Heavy,
+}
+
+impl Damage {
+    pub fn all() -> [Self; 4] {
+        use Damage::*;
+        [None, Light, Moderate, Heavy]
+    }
+}
+
+impl std::fmt::Display for Damage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::None => "None",
+            Self::Light => "Light",
+            Self::Moderate => "Moderate",
+            Self::Heavy => "Heavy",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for Damage {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("damage", Self::all(), s)
+    }
+}
+
+/// What a unit does during the mission, without it units only defend
+/// themselves.
+///
+/// The samples only ever use `NoMission`, the ini strings of the others are
+/// unconfirmed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissionType {
+    NoMission,
+    Patrol,
+    /// hunt submarines
+    Asw,
+    /// defend the rest of the taskforce from air attack
+    AawScreen,
+    /// attack enemy ships and land units
+    Strike,
+    Transit,
+}
+
+impl MissionType {
+    pub fn all() -> [Self; 6] {
+        use MissionType::*;
+        [NoMission, Patrol, Asw, AawScreen, Strike, Transit]
+    }
+
+    /// A mission suited to the unit, used when none is set.
+    pub fn default_for(unit: &db::Unit) -> Self {
+        match unit.utype {
+            UnitType::Submarine | UnitType::Helicopter => Self::Asw,
+            UnitType::Aircraft => Self::Patrol,
+            // escorts screen the larger ships, which are left to the player
+            UnitType::Vessel => match unit.metadata.size_class() {
+                Some(SizeClass::Small) => Self::Patrol,
+                Some(SizeClass::Medium | SizeClass::Large) => Self::AawScreen,
+                Some(SizeClass::Capital) | None => Self::NoMission,
+            },
+            UnitType::LandUnit => Self::NoMission,
+        }
+    }
+}
+
+impl std::fmt::Display for MissionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MissionType::*;
+        let str = match self {
+            NoMission => "NoMission",
+            Patrol => "Patrol",
+            Asw => "ASW",
+            AawScreen => "AAWScreen",
+            Strike => "Strike",
+            Transit => "Transit",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for MissionType {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("mission type", Self::all(), s)
+    }
+}
+
+/// Parts of the names of surface to air missile systems.
+const SAM_SYSTEMS: [&str; 10] = [
+    "SA-N-",
+    "RIM-",
+    "Standard",
+    "Sea Sparrow",
+    "Terrier",
+    "Tartar",
+    "Sea Dart",
+    "Sea Wolf",
+    "Sea Cat",
+    "Crotale",
+];
+/// Parts of the names of air search radars.
+const AIR_SEARCH_RADARS: [&str; 9] = [
+    "SPS-40", "SPS-48", "SPS-49", "SPS-52", "MR-310", "MR-500", "MR-600", "Type 965", "DRBV",
+];
+/// Parts of the names of anti submarine weapons.
+const ASW_WEAPONS: [&str; 7] = [
+    "ASROC", "SUBROC", "Ikara", "SS-N-14", "RBU", "Mk 46", "Torpedo",
+];
+
+/// What an escort in a formation protects the rest of it from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StationRole {
+    Aaw,
+    Asw,
+    Asuw,
+}
+
+impl StationRole {
+    pub fn all() -> [Self; 3] {
+        use StationRole::*;
+        [Aaw, Asw, Asuw]
+    }
+
+    /// The role suited to what the unit is fitted with, `None` if nothing
+    /// is known of its sensors and weapons.
+    ///
+    /// Air defence takes priority, since most escorts also carry a sonar.
+    pub fn suited_to(metadata: &db::UnitMetadata) -> Option<Self> {
+        if metadata.sensors.is_empty() && metadata.weapons.is_empty() {
+            return None;
+        }
+        let has_weapon = |names: &[&str]| names.iter().any(|name| metadata.has_weapon(name));
+        let has_sensor = |names: &[&str]| names.iter().any(|name| metadata.has_sensor(name));
+        if has_weapon(&SAM_SYSTEMS) || has_sensor(&AIR_SEARCH_RADARS) {
+            Some(Self::Aaw)
+        } else if !metadata.sonars.is_empty() || has_weapon(&ASW_WEAPONS) {
+            Some(Self::Asw)
+        } else {
+            Some(Self::Asuw)
+        }
+    }
+
+    /// The role suited to an escort with the given mission, if any.
+    pub fn default_for(mission_type: MissionType) -> Option<Self> {
+        match mission_type {
+            MissionType::AawScreen => Some(Self::Aaw),
+            MissionType::Asw => Some(Self::Asw),
+            MissionType::Strike => Some(Self::Asuw),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StationRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use StationRole::*;
+        let str = match self {
+            Aaw => "AAW",
+            Asw => "ASW",
+            Asuw => "ASuW",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl FromStr for StationRole {
+    type Err = InvalidValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_named("station role", Self::all(), s)
+    }
+}
+
+/// The highest telegraph (speed) setting, flank.
+pub const MAX_TELEGRAPH: u8 = 5;
+
+/// Settings for a unit, any that are `None` are left to whatever the unit
+/// inherits, see `UnitSettings::overlay`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnitSettings {
+    /// speed setting, from 0 (stop) to `MAX_TELEGRAPH`
+    pub telegraph: Option<u8>,
+    pub crew_skill: Option<CrewSkill>,
+    pub radars_active: Option<bool>,
+    pub active_sonars_enabled: Option<bool>,
+    pub towed_array_deployed: Option<bool>,
+    pub towed_decoy_deployed: Option<bool>,
+    pub damage: Option<Damage>,
+    /// falls back to `MissionType::default_for` the unit
+    pub mission_type: Option<MissionType>,
+    /// only escorts in a formation have a role, it falls back to one
+    /// `StationRole::suited_to` them, then `StationRole::default_for` their
+    /// mission
+    pub station_role: Option<StationRole>,
+}
+
+impl UnitSettings {
+    /// The settings the generator has always used by default, everything
+    /// else is left to the game.
+    pub fn taskforce_default() -> Self {
+        Self {
+            telegraph: Some(3),
+            crew_skill: Some(CrewSkill::Trained),
+            ..Default::default()
+        }
+    }
+
+    /// These settings with any set in `other` taking precedence, e.g. a
+    /// unit's settings over its taskforce's.
+    pub fn overlay(&self, other: &Self) -> Self {
+        Self {
+            telegraph: other.telegraph.or(self.telegraph),
+            crew_skill: other.crew_skill.or(self.crew_skill),
+            radars_active: other.radars_active.or(self.radars_active),
+            active_sonars_enabled: other.active_sonars_enabled.or(self.active_sonars_enabled),
+            towed_array_deployed: other.towed_array_deployed.or(self.towed_array_deployed),
+            towed_decoy_deployed: other.towed_decoy_deployed.or(self.towed_decoy_deployed),
+            damage: other.damage.or(self.damage),
+            mission_type: other.mission_type.or(self.mission_type),
+            station_role: other.station_role.or(self.station_role),
+        }
+    }
+
+    /// Whether every setting is inherited.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Write every setting that is set, the game uses its own default for
+    /// the rest.
+    pub fn write_ini(&self, config: &mut Ini, section: &str) {
+        let mut set = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                config.set(section, key, Some(value));
+            }
+        };
+        set("StationRole", self.station_role.map(|r| r.to_string()));
+        set("MissionType", self.mission_type.map(|m| m.to_string()));
+        set("Telegraph", self.telegraph.map(|t| t.to_string()));
+        set("CrewSkill", self.crew_skill.map(|c| c.to_string()));
+        set("RadarsActive", self.radars_active.map(bool_str));
+        set(
+            "ActiveSonarsEnabled",
+            self.active_sonars_enabled.map(bool_str),
+        );
+        set(
+            "TowedArrayDeployed",
+            self.towed_array_deployed.map(bool_str),
+        );
+        set(
+            "TowedDecoyDeployed",
+            self.towed_decoy_deployed.map(bool_str),
+        );
+        set("Damage", self.damage.map(|d| d.to_string()));
+    }
+}
+
+/// A boolean the way the game writes them.
+fn bool_str(value: bool) -> String {
+    match value {
+        true => "True".into(),
+        false => "False".into(),
+    }
+}
+
+/// Parse a boolean the way the game writes them, ignoring case.
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay() {
+        let taskforce = UnitSettings {
+            radars_active: Some(false),
+            ..UnitSettings::taskforce_default()
+        };
+        let unit = UnitSettings {
+            telegraph: Some(5),
+            radars_active: Some(true),
+            ..Default::default()
+        };
+        let settings = taskforce.overlay(&unit);
+        assert_eq!(settings.telegraph, Some(5));
+        assert_eq!(settings.crew_skill, Some(CrewSkill::Trained));
+        assert_eq!(settings.radars_active, Some(true));
+        assert_eq!(settings.damage, None);
+        assert!(UnitSettings::default().is_empty());
+        assert!(!unit.is_empty());
+    }
+
+    #[test]
+    fn test_write_ini() {
+        let settings = UnitSettings {
+            radars_active: Some(false),
+            ..UnitSettings::taskforce_default()
+        };
+        let mut config = Ini::new_cs();
+        settings.write_ini(&mut config, "Unit");
+        assert_eq!(config.get("Unit", "Telegraph").as_deref(), Some("3"));
+        assert_eq!(config.get("Unit", "RadarsActive").as_deref(), Some("False"));
+        assert_eq!(config.get("Unit", "Damage"), None);
+    }
+
+    #[test]
+    fn test_default_mission_type() {
+        let unit_db = crate::unit_db::UnitDb::fixture();
+        let default_for = |id| MissionType::default_for(unit_db.by_id(id).unwrap());
+        assert_eq!(default_for("usn_dd_spruance"), MissionType::AawScreen);
+        assert_eq!(default_for("wp_ss_foxtrot"), MissionType::Asw);
+        assert_eq!(default_for("wp_sam_sa2"), MissionType::NoMission);
+    }
+
+    #[test]
+    fn test_station_role_suited_to() {
+        let metadata = |sensors: &[&str], sonars: &[&str], weapons: &[&str]| {
+            let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+            db::UnitMetadata {
+                sensors: names(sensors),
+                sonars: names(sonars),
+                weapons: names(weapons),
+                ..Default::default()
+            }
+        };
+        let role =
+            |sensors, sonars, weapons| StationRole::suited_to(&metadata(sensors, sonars, weapons));
+        assert_eq!(role(&[], &[], &[]), None);
+        assert_eq!(
+            role(&["MG-312 Titan"], &["MG-312 Titan"], &["SA-N-1 Goa"]),
+            Some(StationRole::Aaw)
+        );
+        assert_eq!(role(&["AN/SPS-49"], &[], &[]), Some(StationRole::Aaw));
+        assert_eq!(
+            role(&["AN/SQS-53"], &["AN/SQS-53"], &["Mk 45"]),
+            Some(StationRole::Asw)
+        );
+        assert_eq!(role(&[], &[], &["RBU-6000"]), Some(StationRole::Asw));
+        assert_eq!(role(&[], &[], &["P-15 Termit"]), Some(StationRole::Asuw));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_bool("True"), Some(true));
+        assert_eq!(parse_bool("false"), Some(false));
+        assert_eq!(parse_bool("1"), None);
+        assert_eq!(
+            "veteran".parse::<CrewSkill>().ok(),
+            Some(CrewSkill::Veteran)
+        );
+        assert_eq!("None".parse::<Damage>().ok(), Some(Damage::None));
+        assert!("Sunk".parse::<Damage>().is_err());
+        assert_eq!(
+            "NoMission".parse::<MissionType>().ok(),
+            Some(MissionType::NoMission)
+        );
+        assert_eq!("asw".parse::<MissionType>().ok(), Some(MissionType::Asw));
+        assert_eq!("AAW".parse::<StationRole>().ok(), Some(StationRole::Aaw));
+        assert_eq!(StationRole::Asuw.to_string(), "ASuW");
+    }
+}
@@ -0,0 +1,483 @@
+//! Check mission options for anything that would be silently dropped, or
+//! rejected by the game, before generating.
+
+use std::collections::HashSet;
+
+use super::{GeneralOptions, MissionOptions, TaskforceOptions, UnitOption};
+use crate::terrain::Terrain;
+use crate::unit_db as db;
+
+/// Warn when less of the mission area than this is sea.
+const MIN_SEA_FRACTION: f32 = 0.25;
+
+/// Something wrong with the mission options.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// the unit isn't in the unit database, e.g. from a mod that has since
+    /// been removed
+    UnknownUnit {
+        taskforce: &'static str,
+        id: String,
+    },
+    /// there is no land area to place a land unit in, `unit` is its id or
+    /// the random query that only matches land units
+    NoLandArea {
+        taskforce: &'static str,
+        unit: String,
+    },
+    /// no unit matches the random query in the mission year
+    EmptyRandomPool {
+        taskforce: &'static str,
+        query: String,
+    },
+    /// the mission area has no width or height
+    ZeroSizeArea,
+    /// the land area has no width or height
+    ZeroSizeLandArea,
+    LatitudeOutOfRange(f32),
+    LongitudeOutOfRange(f32),
+    /// so little of the mission area is sea that ships may end up crowded,
+    /// or on land
+    LittleSea {
+        percent: u8,
+    },
+    /// the minimum range between blue and red is more than the maximum
+    EnemyRangeReversed {
+        min: f32,
+        max: f32,
+    },
+    /// formations are numbered from 1, like in the mission ini
+    EmptyFormation {
+        taskforce: &'static str,
+        formation: usize,
+    },
+    /// a formation of one unit is allowed, but is probably a mistake
+    SingleUnitFormation {
+        taskforce: &'static str,
+        formation: usize,
+    },
+    /// a field of the formation layout, e.g. its name, is empty or contains
+    /// a separator of the formation string
+    InvalidFormationField {
+        taskforce: &'static str,
+        formation: usize,
+        field: &'static str,
+        value: String,
+    },
+    InvalidFormationSpacing {
+        taskforce: &'static str,
+        formation: usize,
+        spacing: f32,
+    },
+    /// found while generating, there was nowhere suitable to place the unit,
+    /// so it may be on land or in shallow water
+    UnsuitablePosition {
+        taskforce: &'static str,
+        unit: String,
+    },
+}
+
+impl Problem {
+    /// Whether the mission can't be generated as is, otherwise it is only a
+    /// warning.
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self,
+            Self::SingleUnitFormation { .. }
+                | Self::LittleSea { .. }
+                | Self::UnsuitablePosition { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownUnit { taskforce, id } => write!(f, "{taskforce}: unknown unit `{id}`"),
+            Self::NoLandArea { taskforce, unit } => {
+                write!(f, "{taskforce}: no land area to place `{unit}` in")
+            }
+            Self::EmptyRandomPool { taskforce, query } if query.is_empty() => {
+                write!(f, "{taskforce}: no units to pick a random unit from")
+            }
+            Self::EmptyRandomPool { taskforce, query } => {
+                write!(
+                    f,
+                    "{taskforce}: no {query} units to pick a random unit from"
+                )
+            }
+            Self::ZeroSizeArea => write!(f, "the mission area has no width or height"),
+            Self::ZeroSizeLandArea => write!(f, "the land area has no width or height"),
+            Self::LatitudeOutOfRange(lat) => {
+                write!(f, "latitude {lat} is not between -90 and 90")
+            }
+            Self::LongitudeOutOfRange(lon) => {
+                write!(f, "longitude {lon} is not between -180 and 180")
+            }
+            Self::LittleSea { percent } => {
+                write!(f, "only {percent}% of the mission area is sea")
+            }
+            Self::EnemyRangeReversed { min, max } => {
+                write!(
+                    f,
+                    "the minimum enemy range {min} is more than the maximum {max}"
+                )
+            }
+            Self::EmptyFormation {
+                taskforce,
+                formation,
+            } => write!(f, "{taskforce}: formation {formation} has no units"),
+            Self::SingleUnitFormation {
+                taskforce,
+                formation,
+            } => write!(f, "{taskforce}: formation {formation} has a single unit"),
+            Self::InvalidFormationField {
+                taskforce,
+                formation,
+                field,
+                value,
+            } => write!(
+                f,
+                "{taskforce}: formation {formation} has an invalid {field} `{value}`"
+            ),
+            Self::InvalidFormationSpacing {
+                taskforce,
+                formation,
+                spacing,
+            } => write!(
+                f,
+                "{taskforce}: formation {formation} has an invalid spacing {spacing}"
+            ),
+            Self::UnsuitablePosition { taskforce, unit } => write!(
+                f,
+                "{taskforce}: nowhere suitable to place `{unit}`, it may be on land or in shallow water"
+            ),
+        }
+    }
+}
+
+impl MissionOptions {
+    /// Every problem with the options, given all the `units` that can be
+    /// generated and the `terrain` they are placed on.
+    pub fn validate<'a, I>(&self, units: I, terrain: &Terrain) -> Vec<Problem>
+    where
+        I: IntoIterator<Item = &'a db::Unit>,
+    {
+        let units: Vec<&db::Unit> = units.into_iter().collect();
+        let mut problems = Vec::new();
+
+        let general = &self.general;
+        let (lat, lon) = general.latlon;
+        if !(-90.0..=90.0).contains(&lat) {
+            problems.push(Problem::LatitudeOutOfRange(lat));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            problems.push(Problem::LongitudeOutOfRange(lon));
+        }
+        if general.size.0 == 0 || general.size.1 == 0 {
+            problems.push(Problem::ZeroSizeArea);
+        }
+        if let Some(land) = &general.land {
+            if land.size.0 == 0 || land.size.1 == 0 {
+                problems.push(Problem::ZeroSizeLandArea);
+            }
+        }
+        if let Some(sea) = terrain.sea_fraction(general.latlon, general.size) {
+            if sea < MIN_SEA_FRACTION {
+                let percent = (sea * 100.0).round() as u8;
+                problems.push(Problem::LittleSea { percent });
+            }
+        }
+        let separation = &general.separation;
+        if let Some((min, max)) = separation.min_enemy_range.zip(separation.max_enemy_range) {
+            if min > max {
+                problems.push(Problem::EnemyRangeReversed { min, max });
+            }
+        }
+
+        let validator = TaskforceValidator {
+            units: &units,
+            ids: units.iter().map(|unit| unit.id.as_str()).collect(),
+            general,
+        };
+        for (taskforce, options) in [
+            ("Neutral", &self.neutral),
+            ("Blue", &self.blue),
+            ("Red", &self.red),
+        ] {
+            validator.validate(taskforce, options, &mut problems);
+        }
+        problems
+    }
+}
+
+struct TaskforceValidator<'a> {
+    units: &'a [&'a db::Unit],
+    ids: HashSet<&'a str>,
+    general: &'a GeneralOptions,
+}
+
+impl TaskforceValidator<'_> {
+    fn validate(
+        &self,
+        taskforce: &'static str,
+        options: &TaskforceOptions,
+        problems: &mut Vec<Problem>,
+    ) {
+        let formation_units = options.formations.iter().flat_map(|f| &f.units);
+        for unit in options.units.iter().chain(formation_units) {
+            self.validate_unit(taskforce, unit, problems);
+        }
+
+        for (idx, formation) in options.formations.iter().enumerate() {
+            let formation_num = idx + 1;
+            match formation.units.len() {
+                0 => problems.push(Problem::EmptyFormation {
+                    taskforce,
+                    formation: formation_num,
+                }),
+                1 => problems.push(Problem::SingleUnitFormation {
+                    taskforce,
+                    formation: formation_num,
+                }),
+                _ => {}
+            }
+
+            let layout = &formation.layout;
+            let fields = [
+                ("name", layout.name.clone()),
+                ("shape", layout.shape.to_string()),
+                ("spawn positions", layout.spawn_positions.to_string()),
+            ];
+            for (field, value) in fields {
+                if value.trim().is_empty() || value.contains(['|', ',']) {
+                    problems.push(Problem::InvalidFormationField {
+                        taskforce,
+                        formation: formation_num,
+                        field,
+                        value,
+                    });
+                }
+            }
+            if !(layout.spacing.is_finite() && layout.spacing > 0.0) {
+                problems.push(Problem::InvalidFormationSpacing {
+                    taskforce,
+                    formation: formation_num,
+                    spacing: layout.spacing,
+                });
+            }
+        }
+    }
+
+    fn validate_unit(
+        &self,
+        taskforce: &'static str,
+        unit: &UnitOption,
+        problems: &mut Vec<Problem>,
+    ) {
+        let problem = match unit {
+            UnitOption::Unit { unit, .. } if !self.ids.contains(unit.id.as_str()) => {
+                Problem::UnknownUnit {
+                    taskforce,
+                    id: unit.id.clone(),
+                }
+            }
+            UnitOption::Unit { unit, .. } if !self.general.can_place(unit.utype) => {
+                Problem::NoLandArea {
+                    taskforce,
+                    unit: unit.id.clone(),
+                }
+            }
+            UnitOption::Random { query, .. } => {
+                // the same query the generator uses
+                let in_service = (**query).clone().in_service(self.general.date.year);
+                let mut pool = self.units.iter().filter(|unit| in_service.matches(unit));
+                match pool.clone().next() {
+                    None => Problem::EmptyRandomPool {
+                        taskforce,
+                        query: query.to_string(),
+                    },
+                    Some(_) if pool.any(|unit| self.general.can_place(unit.utype)) => return,
+                    Some(_) => Problem::NoLandArea {
+                        taskforce,
+                        unit: query.to_string(),
+                    },
+                }
+            }
+            // kept where they were, even if they aren't in the unit database
+            UnitOption::Unit { .. } | UnitOption::Imported { .. } => return,
+        };
+        // the same unit may be added many times, only report it once
+        if !problems.contains(&problem) {
+            problems.push(problem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::{
+        FormationOption, FormationShape, LandArea, StoresOption, UnitSettings, VariantOption,
+    };
+    use crate::unit_db::{UnitDb, UnitQuery, UnitType};
+
+    fn random(query: UnitQuery) -> UnitOption {
+        UnitOption::Random {
+            query: Box::new(query),
+            variant: VariantOption::Default,
+            stores: StoresOption::Full,
+            settings: UnitSettings::default(),
+        }
+    }
+
+    #[test]
+    fn test_validate_general() {
+        let unit_db = UnitDb::fixture();
+        let mut options = MissionOptions::default();
+        assert_eq!(options.validate(unit_db.all(), &Terrain::default()), vec![]);
+
+        options.general.latlon = (91.0, -181.0);
+        options.general.size = (0, 150);
+        options.general.land = Some(LandArea {
+            offset: (0.0, 0.0),
+            size: (10, 0),
+        });
+        options.general.separation.min_enemy_range = Some(50.0);
+        options.general.separation.max_enemy_range = Some(20.0);
+        assert_eq!(
+            options.validate(unit_db.all(), &Terrain::default()),
+            vec![
+                Problem::LatitudeOutOfRange(91.0),
+                Problem::LongitudeOutOfRange(-181.0),
+                Problem::ZeroSizeArea,
+                Problem::ZeroSizeLandArea,
+                Problem::EnemyRangeReversed {
+                    min: 50.0,
+                    max: 20.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_terrain() {
+        let unit_db = UnitDb::fixture();
+        let options = MissionOptions::default();
+        let (lat, lon) = options.general.latlon;
+        let land: Terrain = format!(
+            "ncols 1\nnrows 1\nxllcorner {}\nyllcorner {}\ncellsize 10\n100",
+            lon - 5.0,
+            lat - 5.0
+        )
+        .parse()
+        .unwrap();
+        let problems = options.validate(unit_db.all(), &land);
+        assert_eq!(problems, vec![Problem::LittleSea { percent: 0 }]);
+        assert!(!problems[0].is_error());
+    }
+
+    #[test]
+    fn test_validate_units() {
+        let unit_db = UnitDb::fixture();
+        let mut unknown = unit_db.by_id("wp_bpk_kashin").unwrap().clone();
+        unknown.id = "wp_bpk_kresta".into();
+        let unknown = UnitOption::Unit {
+            unit: Box::new(unknown),
+            variant: VariantOption::Default,
+            stores: StoresOption::Full,
+            settings: UnitSettings::default(),
+        };
+
+        let mut options = MissionOptions::default();
+        options.blue.units = vec![unknown.clone(), unknown];
+        // the spruance entered service in 1975
+        options.general.date.year = 1970;
+        let spruance = random(UnitQuery::new().class("usn_spruance"));
+        options.red.units = vec![spruance.clone(), random(UnitQuery::new())];
+        let sa2 = unit_db.by_id("wp_sam_sa2").unwrap().clone();
+        options.neutral.units = vec![
+            UnitOption::Unit {
+                unit: Box::new(sa2),
+                variant: VariantOption::Default,
+                stores: StoresOption::Full,
+                settings: UnitSettings::default(),
+            },
+            random(UnitQuery::new().utype(UnitType::LandUnit)),
+        ];
+        options.red.formations = vec![
+            FormationOption::new(vec![]),
+            FormationOption::new(vec![random(UnitQuery::new().utype(UnitType::Vessel))]),
+        ];
+
+        let problems = options.validate(unit_db.all(), &Terrain::default());
+        assert_eq!(
+            problems,
+            vec![
+                Problem::NoLandArea {
+                    taskforce: "Neutral",
+                    unit: "wp_sam_sa2".into(),
+                },
+                Problem::NoLandArea {
+                    taskforce: "Neutral",
+                    unit: "Land Unit".into(),
+                },
+                Problem::UnknownUnit {
+                    taskforce: "Blue",
+                    id: "wp_bpk_kresta".into(),
+                },
+                Problem::EmptyRandomPool {
+                    taskforce: "Red",
+                    query: "usn_spruance class".into(),
+                },
+                Problem::EmptyFormation {
+                    taskforce: "Red",
+                    formation: 1,
+                },
+                Problem::SingleUnitFormation {
+                    taskforce: "Red",
+                    formation: 2,
+                },
+            ]
+        );
+        assert!(problems[0].is_error());
+        assert!(!problems[5].is_error());
+    }
+
+    #[test]
+    fn test_validate_formation_layout() {
+        let unit_db = UnitDb::fixture();
+        let vessel = || random(UnitQuery::new().utype(UnitType::Vessel));
+        let mut formation = FormationOption::new(vec![vessel(), vessel()]);
+        formation.layout.name = "Group|1".into();
+        formation.layout.shape = FormationShape::Other("".into());
+        formation.layout.spacing = 0.0;
+        let mut options = MissionOptions::default();
+        options.blue.formations = vec![formation];
+
+        let problems = options.validate(unit_db.all(), &Terrain::default());
+        assert_eq!(
+            problems,
+            vec![
+                Problem::InvalidFormationField {
+                    taskforce: "Blue",
+                    formation: 1,
+                    field: "name",
+                    value: "Group|1".into(),
+                },
+                Problem::InvalidFormationField {
+                    taskforce: "Blue",
+                    formation: 1,
+                    field: "shape",
+                    value: "".into(),
+                },
+                Problem::InvalidFormationSpacing {
+                    taskforce: "Blue",
+                    formation: 1,
+                    spacing: 0.0,
+                },
+            ]
+        );
+        assert!(problems.iter().all(Problem::is_error));
+    }
+}
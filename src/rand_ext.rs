@@ -1,7 +1,23 @@
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-pub fn position(size: &(u16, u16)) -> (f32, f32) {
-    let mut rng = thread_rng();
+/// The random number generator everything in a mission is generated with,
+/// the same seed always generates the same mission.
+///
+/// Unlike `StdRng`, its output is guaranteed to be the same on every platform
+/// and version, so seeds can be shared.
+pub type MissionRng = ChaCha8Rng;
+
+pub fn seeded(seed: u64) -> MissionRng {
+    MissionRng::seed_from_u64(seed)
+}
+
+/// A new seed, for when the user doesn't give one.
+pub fn random_seed() -> u64 {
+    thread_rng().gen()
+}
+
+pub fn position(rng: &mut impl Rng, size: &(u16, u16)) -> (f32, f32) {
     let (w, h) = *size;
     let half_w = w as f32 / 2.0;
     let half_h = h as f32 / 2.0;
@@ -11,6 +27,6 @@ pub fn position(size: &(u16, u16)) -> (f32, f32) {
     )
 }
 
-pub fn heading() -> u16 {
-    thread_rng().gen_range(0..360)
+pub fn heading(rng: &mut impl Rng) -> u16 {
+    rng.gen_range(0..360)
 }
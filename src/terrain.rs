@@ -0,0 +1,273 @@
+//! Land and water depth around the mission area, so that ships aren't placed
+//! on land.
+//!
+//! The game doesn't expose its own map, so this is read from an elevation
+//! grid the user provides, e.g. a GEBCO or ETOPO extract exported as an
+//! [ESRI ASCII grid](https://en.wikipedia.org/wiki/Esri_grid).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// How many points along each side of the mission area are sampled by
+/// `Terrain::sea_fraction`.
+const SEA_SAMPLES: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum TerrainError {
+    #[error("failed to read elevation grid {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid elevation grid: {0}")]
+    Parse(String),
+}
+
+/// An elevation grid in metres, negative below sea level.
+///
+/// An empty grid (the default) has no data, so everywhere is assumed to be
+/// open sea, like before any grid is provided.
+#[derive(Clone, Debug, Default)]
+pub struct Terrain {
+    cols: usize,
+    rows: usize,
+    /// longitude of the western edge
+    west: f32,
+    /// latitude of the southern edge
+    south: f32,
+    /// the size of each cell in degrees
+    cell_size: f32,
+    nodata: Option<f32>,
+    /// row by row, starting from the northernmost
+    values: Vec<f32>,
+}
+
+impl Terrain {
+    /// Load an ESRI ASCII grid from `path`, if it doesn't exist there is no
+    /// data.
+    pub fn load(path: &Path) -> Result<Self, TerrainError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path).map_err(|source| TerrainError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        contents.parse()
+    }
+
+    /// Whether there is any data, without it everywhere is assumed to be sea.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The elevation at the given latitude and longitude, `None` if it isn't
+    /// covered by the grid.
+    pub fn elevation(&self, (lat, lon): (f32, f32)) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        let col = ((lon - self.west) / self.cell_size).floor();
+        let row_from_south = ((lat - self.south) / self.cell_size).floor();
+        if col < 0.0 || row_from_south < 0.0 {
+            return None;
+        }
+        let (col, row_from_south) = (col as usize, row_from_south as usize);
+        if col >= self.cols || row_from_south >= self.rows {
+            return None;
+        }
+        let row = self.rows - 1 - row_from_south;
+        let value = self.values[row * self.cols + col];
+        match self.nodata {
+            Some(nodata) if value == nodata => None,
+            _ => Some(value),
+        }
+    }
+
+    /// The fraction of a mission area of `size` (w,h) in nm around `latlon`
+    /// that is sea, `None` if the grid doesn't cover any of it.
+    pub fn sea_fraction(&self, latlon: (f32, f32), size: (u16, u16)) -> Option<f32> {
+        let step = |length: u16, idx: usize| {
+            let length = length as f32;
+            (idx as f32 + 0.5) / SEA_SAMPLES as f32 * length - length / 2.0
+        };
+        let mut sea = 0;
+        let mut covered = 0;
+        for x in 0..SEA_SAMPLES {
+            for y in 0..SEA_SAMPLES {
+                let point = (step(size.0, x), step(size.1, y));
+                if let Some(elevation) = self.elevation(offset_latlon(latlon, point)) {
+                    covered += 1;
+                    if elevation < 0.0 {
+                        sea += 1;
+                    }
+                }
+            }
+        }
+        (covered > 0).then(|| sea as f32 / covered as f32)
+    }
+}
+
+impl std::str::FromStr for Terrain {
+    type Err = TerrainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |key: &str, value: &str| TerrainError::Parse(format!("invalid {key} `{value}`"));
+        let mut tokens = s.split_whitespace().peekable();
+        // the header is in a fixed order, some keys have alternative names
+        let mut header = |names: &[&str]| -> Result<(String, &str), TerrainError> {
+            let expected = || TerrainError::Parse(format!("expected {}", names.join(" or ")));
+            let key = tokens
+                .next()
+                .filter(|key| names.iter().any(|name| key.eq_ignore_ascii_case(name)))
+                .ok_or_else(expected)?;
+            let value = tokens
+                .next()
+                .ok_or_else(|| TerrainError::Parse(format!("missing {key}")))?;
+            Ok((key.to_lowercase(), value))
+        };
+        let count = |(key, value): (String, &str)| match value.parse() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(invalid(&key, value)),
+        };
+        let number = |(key, value): (String, &str)| match value.parse::<f32>() {
+            Ok(number) if number.is_finite() => Ok((key, number)),
+            _ => Err(invalid(&key, value)),
+        };
+        let cols: usize = count(header(&["ncols"])?)?;
+        let rows: usize = count(header(&["nrows"])?)?;
+        let (x_key, x) = number(header(&["xllcorner", "xllcenter"])?)?;
+        let (y_key, y) = number(header(&["yllcorner", "yllcenter"])?)?;
+        let (cell_key, cell_size) = number(header(&["cellsize"])?)?;
+        if cell_size <= 0.0 {
+            return Err(invalid(&cell_key, &cell_size.to_string()));
+        }
+        let cells = cols
+            .checked_mul(rows)
+            .ok_or_else(|| TerrainError::Parse(format!("too many cells, {cols}x{rows}")))?;
+
+        // the nodata value is optional, the values follow without it
+        let mut values = Vec::new();
+        let mut nodata = None;
+        if let Some(key) = tokens.next_if(|key| key.eq_ignore_ascii_case("nodata_value")) {
+            let value = tokens.next().unwrap_or_default();
+            nodata = Some(value.parse().map_err(|_| invalid(key, value))?);
+        }
+        for token in tokens {
+            let value = token
+                .parse()
+                .map_err(|_| TerrainError::Parse(format!("invalid elevation `{token}`")))?;
+            values.push(value);
+        }
+        if values.len() != cells {
+            return Err(TerrainError::Parse(format!(
+                "expected {cells} elevations, found {}",
+                values.len()
+            )));
+        }
+
+        // the origin is either the corner or centre of the south west cell
+        let corner = |key: &str, value: f32| match key.ends_with("center") {
+            true => value - cell_size / 2.0,
+            false => value,
+        };
+        Ok(Self {
+            cols,
+            rows,
+            west: corner(&x_key, x),
+            south: corner(&y_key, y),
+            cell_size,
+            nodata,
+            values,
+        })
+    }
+}
+
+/// The latitude and longitude of a `point` (x,y) in nm east and north of
+/// `latlon`.
+pub fn offset_latlon((lat, lon): (f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+    // a minute of latitude is a nautical mile, minutes of longitude shrink
+    // towards the poles
+    let lat_offset = y / 60.0;
+    let lon_offset = x / (60.0 * lat.to_radians().cos().max(0.01));
+    (lat + lat_offset, lon + lon_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Land in the western column, shallow water in the middle and deep
+    /// water in the east. Each cell is a degree.
+    const GRID: &str = "ncols 3
+nrows 2
+xllcorner 10
+yllcorner 40
+cellsize 1
+NODATA_value -9999
+120 -20 -3000
+15 -9999 -2500
+";
+
+    #[test]
+    fn test_elevation() {
+        let terrain: Terrain = GRID.parse().unwrap();
+        assert_eq!(terrain.elevation((41.5, 10.5)), Some(120.0));
+        assert_eq!(terrain.elevation((40.5, 10.5)), Some(15.0));
+        assert_eq!(terrain.elevation((41.5, 12.9)), Some(-3000.0));
+        assert_eq!(terrain.elevation((40.5, 11.5)), None);
+        assert_eq!(terrain.elevation((39.5, 10.5)), None);
+        assert_eq!(terrain.elevation((41.5, 13.5)), None);
+        assert_eq!(Terrain::default().elevation((41.5, 10.5)), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("ncols 2\nnrows 1\nxllcorner 0\nyllcorner 0\ncellsize 1\n1"
+            .parse::<Terrain>()
+            .is_err());
+        let centred: Terrain = "ncols 1\nnrows 1\nxllcenter 0.5\nyllcenter 0.5\ncellsize 1\n-5"
+            .parse()
+            .unwrap();
+        assert_eq!(centred.elevation((0.1, 0.1)), Some(-5.0));
+        let header = |ncols, nrows, cellsize| {
+            format!("ncols {ncols}\nnrows {nrows}\nxllcorner 0\nyllcorner 0\ncellsize {cellsize}\n")
+        };
+        for (ncols, nrows, cellsize) in [
+            ("0", "1", "1"),
+            ("-1", "1", "1"),
+            ("1.5", "1", "1"),
+            ("1", "1", "0"),
+            ("1", "1", "-1"),
+            // would overflow the number of cells
+            ("4294967296", "4294967296", "1"),
+        ] {
+            let grid = header(ncols, nrows, cellsize) + "1";
+            assert!(grid.parse::<Terrain>().is_err(), "{grid}");
+        }
+        // the keys are checked, not just their position
+        assert!(
+            "nrows 1\nncols 2\nxllcorner 0\nyllcorner 0\ncellsize 1\n1 2"
+                .parse::<Terrain>()
+                .is_err()
+        );
+        assert!("ncols 1\nnrows 1\nxll 0\nyll 0\ncellsize 1\n1"
+            .parse::<Terrain>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_sea_fraction() {
+        let terrain: Terrain = GRID.parse().unwrap();
+        // a minute of latitude is a nm
+        assert_eq!(offset_latlon((41.0, 12.0), (0.0, 30.0)), (41.5, 12.0));
+        assert_eq!(terrain.sea_fraction((41.5, 12.5), (10, 10)), Some(1.0));
+        assert_eq!(terrain.sea_fraction((41.5, 10.5), (10, 10)), Some(0.0));
+        assert_eq!(terrain.sea_fraction((0.0, 0.0), (10, 10)), None);
+    }
+}
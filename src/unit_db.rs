@@ -1,22 +1,52 @@
+mod cache;
+mod era;
+#[cfg(test)]
+mod fixture;
+mod layer;
+mod metadata;
+mod query;
+mod report;
+mod search;
+mod source;
+
 use std::{collections::HashMap, path::PathBuf};
 
 use crate::dir;
 use configparser::ini::Ini;
-use std::{fs, io, path::Path};
+use std::{io, path::Path};
 use thiserror::Error;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub use cache::Cache;
+pub use era::{EraOverrides, ServiceYears};
+use layer::Layer;
+pub use layer::UnitSource;
+pub use metadata::{SizeClass, UnitMetadata};
+pub use query::UnitQuery;
+pub use report::LoadReport;
+use report::SkipReason;
+pub use source::{FileSource, FsSource};
+
+/// The language every unit is guaranteed to have a name in.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Unit names keyed by language code and then by unit id, the first name is
+/// that of the class, followed by the names of each hull.
+type UnitNames = HashMap<String, HashMap<String, Vec<String>>>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum UnitType {
     Aircraft,
     Submarine,
     Helicopter,
     Vessel,
+    /// SAM sites, coastal batteries, radar stations, etc.
+    LandUnit,
 }
 
 impl UnitType {
-    pub fn all() -> [Self; 4] {
+    pub fn all() -> [Self; 5] {
         use UnitType::*;
-        [Vessel, Submarine, Helicopter, Aircraft]
+        [Vessel, Submarine, Helicopter, Aircraft, LandUnit]
     }
 
     pub fn capitalised_singular(&self) -> String {
@@ -25,6 +55,7 @@ impl UnitType {
             Self::Submarine => "Submarine",
             Self::Aircraft => "Aircraft",
             Self::Helicopter => "Helicopter",
+            Self::LandUnit => "LandUnit",
         };
         str.to_owned()
     }
@@ -40,7 +71,10 @@ impl UnitType {
 
 impl std::fmt::Display for UnitType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.capitalised_singular())
+        match self {
+            Self::LandUnit => write!(f, "Land Unit"),
+            _ => write!(f, "{}", self.capitalised_singular()),
+        }
     }
 }
 
@@ -57,6 +91,7 @@ impl TryFrom<String> for UnitType {
             "submarine" => Ok(Self::Submarine),
             "helicopter" => Ok(Self::Helicopter),
             "aircraft" => Ok(Self::Aircraft),
+            "landunit" | "land unit" | "land" => Ok(Self::LandUnit),
             _ => Err(UnknownUnitTypeError(utype.to_owned())),
         }
     }
@@ -85,9 +120,54 @@ impl std::fmt::Display for Nation {
 #[derive(Clone, Debug)]
 pub struct Unit {
     pub id: String,
+    /// the english name of the unit
     pub name: String,
+    /// map of language code => name, for every translation of `name`
+    pub localised_names: HashMap<String, String>,
+    /// the english names of each individual hull (ship) of the unit, e.g.
+    /// `USS Spruance`
+    pub hull_names: Vec<String>,
+    /// shared by every unit of the same class, e.g. the Kashin and Kashin
+    /// Mod both belong to `wp_kashin`
+    pub class_id: String,
     pub nation: Nation,
     pub utype: UnitType,
+    /// references of the non-default variants (fits) available for this unit
+    pub variants: Vec<String>,
+    pub metadata: UnitMetadata,
+    /// the layer the unit was loaded from, if overridden by a mod or the
+    /// user this will be the highest precedence layer.
+    pub source: UnitSource,
+}
+
+impl Unit {
+    /// The name of the unit in the given language, falls back to english if
+    /// there is no translation.
+    pub fn name_in(&self, language: &str) -> &str {
+        self.localised_names.get(language).unwrap_or(&self.name)
+    }
+
+    /// Derive the class of the unit `id`, Sea Power ids are structured like
+    /// `<nation>_<hull>_<class>[_<subclass>]`, so the hull and any subclass
+    /// are dropped. Ids that don't follow this are their own class.
+    pub fn class_id_of(id: &str) -> String {
+        match split_id(id) {
+            Some((nation, _, class)) => format!("{nation}_{class}"),
+            None => id.to_owned(),
+        }
+    }
+
+    /// A copy of the unit with `name` translated to the given language, the
+    /// english name is kept with the other translations.
+    pub fn localised(&self, language: &str) -> Self {
+        let mut localised_names = self.localised_names.clone();
+        localised_names.insert(DEFAULT_LANGUAGE.to_owned(), self.name.clone());
+        Self {
+            name: self.name_in(language).to_owned(),
+            localised_names,
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -104,15 +184,73 @@ pub struct UnitDb {
     nations: HashMap<String, Nation>,
     /// map of unit id => unit
     units: HashMap<String, Unit>,
+    /// codes of all installed languages, e.g. `en`
+    languages: Vec<String>,
 }
 
 impl UnitDb {
-    pub fn new(root_dir: &Path) -> Result<Self, UnitDbError> {
-        let nations = load_nation_reference(root_dir)?;
+    /// Load all units from the game in `root_dir`, any file that hasn't
+    /// changed since it was last read is fetched from the `cache` instead.
+    ///
+    /// Unit files that can't be used are skipped and listed in the returned
+    /// `LoadReport`.
+    pub fn new(root_dir: &Path, cache: &mut Cache) -> Result<(Self, LoadReport), UnitDbError> {
+        Self::from_source(&FsSource, root_dir, cache)
+    }
+
+    /// Same as `new`, but reads every game file from `source`.
+    pub fn from_source(
+        source: &dyn FileSource,
+        root_dir: &Path,
+        cache: &mut Cache,
+    ) -> Result<(Self, LoadReport), UnitDbError> {
+        let mut report = LoadReport::default();
+        let layers = layer::find_layers(source, root_dir)?;
+        let nations = load_nation_reference(source, &layers, cache)?;
+        let languages = find_languages(source, &layers)?;
         let mut units = HashMap::new();
-        units.extend(load_vessels(root_dir, &nations)?);
-        // units.extend(load_aircraft(root_dir)?);
-        Ok(Self { nations, units })
+        units.extend(load_vessels(
+            source,
+            &layers,
+            &nations,
+            &languages,
+            cache,
+            &mut report,
+        )?);
+        units.extend(load_aircraft(
+            source,
+            &layers,
+            &nations,
+            &languages,
+            cache,
+            &mut report,
+        )?);
+        units.extend(load_land_units(
+            source,
+            &layers,
+            &nations,
+            &languages,
+            cache,
+            &mut report,
+        )?);
+        let unit_db = Self {
+            nations,
+            units,
+            languages,
+        };
+        Ok((unit_db, report))
+    }
+
+    /// Replace the service years of every unit with those from the user's
+    /// `overrides`.
+    pub fn apply_era_overrides(&mut self, overrides: &EraOverrides) {
+        for (id, unit) in &mut self.units {
+            overrides.apply(id, &mut unit.metadata.service);
+        }
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.languages
     }
 
     pub fn nations(&self) -> Vec<&Nation> {
@@ -129,19 +267,88 @@ impl UnitDb {
         self.units.get(id)
     }
 
-    pub fn search(&self, nation: Option<Nation>, utype: Option<UnitType>) -> Vec<&Unit> {
-        self.all()
+    /// All units matching the `query`, ordered by id so that the same
+    /// query always gives the same result.
+    pub fn search(&self, query: &UnitQuery) -> Vec<&Unit> {
+        let mut units: Vec<&Unit> = self.units.values().filter(|u| query.matches(u)).collect();
+        units.sort_by(|a, b| a.id.cmp(&b.id));
+        units
+    }
+
+    /// Rank every unit by how well it matches `query`, best matches first,
+    /// see `search::fuzzy_search`. Equal matches are ordered by id.
+    pub fn fuzzy_search(&self, query: &str) -> Vec<&Unit> {
+        let mut units = self.all();
+        units.sort_by(|a, b| a.id.cmp(&b.id));
+        search::fuzzy_search(units, query)
+    }
+
+    /// A copy of the database with every unit named in the given `language`.
+    pub fn localised(&self, language: &str) -> Self {
+        let units = self
+            .units
             .iter()
-            .filter(|v| nation.clone().map(|n| v.nation == n).unwrap_or(true))
-            .filter(|v| utype.map(|s| v.utype == s).unwrap_or(true))
-            .copied()
-            .collect()
+            .map(|(id, unit)| (id.clone(), unit.localised(language)))
+            .collect();
+        Self {
+            nations: self.nations.clone(),
+            units,
+            languages: self.languages.clone(),
+        }
+    }
+
+    /// A database of just `units`, with the nations they belong to.
+    #[cfg(test)]
+    pub fn from_units(units: impl IntoIterator<Item = Unit>) -> Self {
+        let units: HashMap<String, Unit> = units
+            .into_iter()
+            .map(|unit| (unit.id.clone(), unit))
+            .collect();
+        let nations = units
+            .values()
+            .map(|unit| (unit.nation.id.clone(), unit.nation.clone()))
+            .collect();
+        Self {
+            nations,
+            units,
+            languages: vec![DEFAULT_LANGUAGE.to_owned()],
+        }
+    }
+
+    /// The fixture database, see `fixture::source`.
+    #[cfg(test)]
+    pub fn fixture() -> Self {
+        let root = Path::new(fixture::ROOT);
+        let mut cache = Cache::new(root);
+        let (unit_db, _) = Self::from_source(&fixture::source(), root, &mut cache).unwrap();
+        unit_db
     }
 }
 
-fn load_nation_reference(root_dir: &Path) -> Result<HashMap<String, Nation>, UnitDbError> {
-    let config_file = dir::original_dir(root_dir).join("nations_reference.ini");
-    let config = load_ini(&config_file)?;
+/// Load nations from every layer, only the original data is required to have
+/// a nation reference.
+fn load_nation_reference(
+    source: &dyn FileSource,
+    layers: &[Layer],
+    cache: &mut Cache,
+) -> Result<HashMap<String, Nation>, UnitDbError> {
+    let mut nations = HashMap::new();
+    for layer in layers {
+        let config_file = layer.data_dir.join("nations_reference.ini");
+        if !layer.is_original() && !source.is_file(&config_file) {
+            continue;
+        }
+        nations.extend(cache.fetch(source, &config_file, || load_nations(source, &config_file))?);
+    }
+    Ok(nations)
+}
+
+/// Load a map of nation id => nation from a single nation reference file.
+fn load_nations(
+    source: &dyn FileSource,
+    config_file: &Path,
+) -> Result<HashMap<String, Nation>, UnitDbError> {
+    let config = load_ini(source, config_file)?;
 
     let mut nations = HashMap::new();
     if let Some(map) = config.get_map() {
@@ -160,92 +367,358 @@ fn load_nation_reference(root_dir: &Path) -> Result<HashMap<String, Nation>, Uni
     Ok(nations)
 }
 
+/// Find the codes of all languages installed in any layer, english is always
+/// included since it's required.
+fn find_languages(source: &dyn FileSource, layers: &[Layer]) -> Result<Vec<String>, UnitDbError> {
+    let mut languages = vec![DEFAULT_LANGUAGE.to_owned()];
+    for layer in layers {
+        for path in source.read_dir(&layer.data_dir)? {
+            let language = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(dir::language_code);
+            match language {
+                Some(language)
+                    if source.is_dir(&path) && !languages.iter().any(|l| l == language) =>
+                {
+                    languages.push(language.to_owned())
+                }
+                _ => continue,
+            }
+        }
+    }
+    languages.sort();
+    Ok(languages)
+}
+
 fn load_vessels(
-    root_dir: &Path,
+    source: &dyn FileSource,
+    layers: &[Layer],
     nations: &HashMap<String, Nation>,
+    languages: &[String],
+    cache: &mut Cache,
+    report: &mut LoadReport,
 ) -> Result<HashMap<String, Unit>, UnitDbError> {
-    let names = load_vessel_names(root_dir)?;
-    let mut vessels = HashMap::new();
-
-    for entry in fs::read_dir(dir::vessel_dir(root_dir))? {
-        let path = entry?.path();
-        let id = match path_to_id(&path) {
-            // skip storing variants for now, TODO
-            Some(id) if !id.ends_with("_variants") => id.to_string(),
-            _ => continue, // skip invalid or variant IDjs
-        };
+    let names = load_unit_names(source, layers, languages, "vessel_names.ini", true, cache)?;
+    let loader = UnitLoader {
+        source,
+        names: &names,
+        nations,
+        cache,
+        report,
+    };
+    loader.load(layers, dir::vessel_dir, true)
+}
+
+/// Load all fixed wing aircraft and helicopters, both are stored in the same
+/// directory and are told apart by their `UnitType`.
+fn load_aircraft(
+    source: &dyn FileSource,
+    layers: &[Layer],
+    nations: &HashMap<String, Nation>,
+    languages: &[String],
+    cache: &mut Cache,
+    report: &mut LoadReport,
+) -> Result<HashMap<String, Unit>, UnitDbError> {
+    let names = load_unit_names(source, layers, languages, "aircraft_names.ini", true, cache)?;
+    let loader = UnitLoader {
+        source,
+        names: &names,
+        nations,
+        cache,
+        report,
+    };
+    loader.load(layers, dir::aircraft_dir, true)
+}
+
+/// Load all land units, unlike other units these are optional since not
+/// every version of the game (or mod) ships with them.
+fn load_land_units(
+    source: &dyn FileSource,
+    layers: &[Layer],
+    nations: &HashMap<String, Nation>,
+    languages: &[String],
+    cache: &mut Cache,
+    report: &mut LoadReport,
+) -> Result<HashMap<String, Unit>, UnitDbError> {
+    let names = load_unit_names(
+        source,
+        layers,
+        languages,
+        "land_unit_names.ini",
+        false,
+        cache,
+    )?;
+    let loader = UnitLoader {
+        source,
+        names: &names,
+        nations,
+        cache,
+        report,
+    };
+    loader.load(layers, dir::land_unit_dir, false)
+}
+
+/// The parts of a unit that are read from its own ini file.
+#[derive(Clone, Debug)]
+struct UnitFile {
+    /// the raw `General.UnitType`, if any
+    utype: Option<String>,
+    metadata: UnitMetadata,
+}
+
+fn load_unit_file(source: &dyn FileSource, id: &str, path: &Path) -> Result<UnitFile, UnitDbError> {
+    let config = load_ini(source, path)?;
+    Ok(UnitFile {
+        utype: config.get("General", "UnitType"),
+        metadata: UnitMetadata::from_ini(id, &config),
+    })
+}
+
+/// Everything needed to turn unit files into units.
+struct UnitLoader<'a> {
+    source: &'a dyn FileSource,
+    names: &'a UnitNames,
+    nations: &'a HashMap<String, Nation>,
+    cache: &'a mut Cache,
+    report: &'a mut LoadReport,
+}
+
+impl UnitLoader<'_> {
+    /// Load every unit ini within the `unit_dir` of each layer, resolving
+    /// their names from `names` and their nation from the unit id prefix.
+    ///
+    /// A unit in a later layer replaces any unit with the same id in an
+    /// earlier layer. If `required`, the original data must have a
+    /// `unit_dir`.
+    fn load(
+        mut self,
+        layers: &[Layer],
+        unit_dir: fn(&Path) -> PathBuf,
+        required: bool,
+    ) -> Result<HashMap<String, Unit>, UnitDbError> {
+        // map of unit id => (unit file, source), we only want to parse the
+        // file from the highest precedence layer.
+        let mut unit_files = HashMap::new();
+        // map of unit id => variants file, attached once all units are known
+        // since a layer may only override the variants of a unit.
+        let mut variant_files = HashMap::new();
+
+        for layer in layers {
+            let unit_dir = unit_dir(&layer.data_dir);
+            // mods may only touch some unit types, original data is required
+            let required = required && layer.is_original();
+            if !required && !self.source.is_dir(&unit_dir) {
+                continue;
+            }
+
+            for path in self.source.read_dir(&unit_dir)? {
+                let id = match path_to_id(&path) {
+                    Some(id) => id.to_string(),
+                    None => {
+                        self.report.skip(&path, SkipReason::InvalidFileName);
+                        continue;
+                    }
+                };
 
+                if let Some(unit_id) = id.strip_suffix("_variants") {
+                    variant_files.insert(unit_id.to_string(), path);
+                } else {
+                    unit_files.insert(id, (path, layer.source.clone()));
+                }
+            }
+        }
+
+        let mut units = HashMap::new();
+        for (id, (path, source)) in &unit_files {
+            match self.load_unit(id, path, source)? {
+                Ok(unit) => {
+                    units.insert(id.clone(), unit);
+                }
+                Err(reason) => self.report.skip(path, reason),
+            }
+        }
+
+        for (id, path) in variant_files {
+            if let Some(unit) = units.get_mut(&id) {
+                let source = self.source;
+                unit.variants = self
+                    .cache
+                    .fetch(source, &path, || load_variants(source, &path))?;
+            } else if !unit_files.contains_key(&id) {
+                // otherwise the unit has already been reported
+                self.report.skip(&path, SkipReason::VariantsWithoutUnit);
+            }
+        }
+        Ok(units)
+    }
+
+    /// Load a single unit, returning why the unit was skipped if it can't
+    /// be used.
+    fn load_unit(
+        &mut self,
+        id: &str,
+        path: &Path,
+        source: &UnitSource,
+    ) -> Result<Result<Unit, SkipReason>, UnitDbError> {
         let (nation_id, _) = match id.split_once("_") {
             Some(split) => split,
-            None => continue,
+            None => return Ok(Err(SkipReason::MissingNationPrefix)),
         };
 
-        // skip vessels without names, if they don't have one, they're
+        // skip units without names, if they don't have one, they're
         // probably not important enough to include.
-        let name = match names.get(&id) {
-            Some(id) => id.to_string(),
-            _ => continue,
+        let (name, hull_names) = match self
+            .names
+            .get(DEFAULT_LANGUAGE)
+            .and_then(|n| n.get(id)?.split_first())
+        {
+            Some((name, hull_names)) => (name.clone(), hull_names.to_vec()),
+            _ => return Ok(Err(SkipReason::MissingName)),
         };
+        let localised_names = self
+            .names
+            .iter()
+            .filter(|(language, _)| *language != DEFAULT_LANGUAGE)
+            .filter_map(|(language, n)| Some((language.clone(), n.get(id)?.first()?.clone())))
+            .collect();
 
-        let nation = match nations.get(nation_id) {
+        let nation = match self.nations.get(nation_id) {
             Some(nation) => nation.clone(),
-            None => continue, // skip invalid nations
+            None => return Ok(Err(SkipReason::UnknownNation(nation_id.to_owned()))),
         };
 
-        let config = load_ini(&path)?;
+        let files = self.source;
+        let UnitFile { utype, metadata } = self
+            .cache
+            .fetch(files, path, || load_unit_file(files, id, path))?;
 
-        let utype = match config
-            .get("General", "UnitType")
-            .and_then(|utype| UnitType::try_from(utype).ok())
-        {
-            Some(utype) => utype,
-            None => continue, // skip invalid types
+        let utype = match utype.map(UnitType::try_from) {
+            Some(Ok(utype)) => utype,
+            Some(Err(UnknownUnitTypeError(utype))) => {
+                return Ok(Err(SkipReason::UnknownUnitType(utype)))
+            }
+            None => return Ok(Err(SkipReason::MissingUnitType)),
         };
 
-        vessels.insert(
-            id.clone(),
-            Unit {
-                id,
-                name,
-                nation,
-                utype,
-            },
-        );
+        Ok(Ok(Unit {
+            id: id.to_owned(),
+            name,
+            localised_names,
+            hull_names,
+            class_id: Unit::class_id_of(id),
+            nation,
+            utype,
+            variants: Vec::new(),
+            metadata,
+            source: source.clone(),
+        }))
+    }
+}
+
+/// Load the variant references from a `<unit>_variants.ini` file.
+///
+/// Each section of the file describes a single variant, where the section
+/// name is what a mission refers to with `VariantReference`. The game
+/// default is implied and so isn't included.
+fn load_variants(source: &dyn FileSource, path: &Path) -> Result<Vec<String>, UnitDbError> {
+    // variant references are case sensitive
+    let mut config = Ini::new_cs();
+    config
+        .read(source.read_to_string(path)?)
+        .map_err(|reason| UnitDbError::IniParse {
+            file: path.to_owned(),
+            reason,
+        })?;
+
+    let mut variants: Vec<String> = config
+        .sections()
+        .into_iter()
+        .filter(|section| !is_variant_ignored(section))
+        .collect();
+    variants.sort();
+    Ok(variants)
+}
+
+/// Sections within a variants file that don't describe a variant.
+fn is_variant_ignored(section: &str) -> bool {
+    ["default", "general"].contains(&section.to_lowercase().as_str())
+}
+
+/// Load the unit names from `file` in each of the given languages, names
+/// from later layers replace those of earlier layers.
+///
+/// Only the original english file is `required`, translations and other
+/// layers may be missing or incomplete.
+fn load_unit_names(
+    source: &dyn FileSource,
+    layers: &[Layer],
+    languages: &[String],
+    file: &str,
+    required: bool,
+    cache: &mut Cache,
+) -> Result<UnitNames, UnitDbError> {
+    let mut names: UnitNames = HashMap::new();
+    for layer in layers {
+        for language in languages {
+            let config_file = dir::language_dir(&layer.data_dir, language).join(file);
+            let required = required && layer.is_original() && language == DEFAULT_LANGUAGE;
+            if !required && !source.is_file(&config_file) {
+                continue;
+            }
+            names
+                .entry(language.clone())
+                .or_default()
+                .extend(cache.fetch(source, &config_file, || load_names(source, &config_file))?);
+        }
     }
-    Ok(vessels)
+    Ok(names)
 }
 
-fn load_vessel_names(root_dir: &Path) -> Result<HashMap<String, String>, UnitDbError> {
-    let config_file = dir::original_dir(root_dir).join("language_en/vessel_names.ini");
-    let config = load_ini(&config_file)?;
+/// Load a map of unit id => unit names from a single language file, see
+/// `UnitNames`.
+fn load_names(
+    source: &dyn FileSource,
+    config_file: &Path,
+) -> Result<HashMap<String, Vec<String>>, UnitDbError> {
+    let config = load_ini(source, config_file)?;
     let mut names = HashMap::new();
     if let Some(map) = config.get_map() {
         for (id, config) in map {
             let default = config.get("default").and_then(|o| (*o).clone());
             if let Some(name_parts) = default {
-                let name = split_name_parts(&name_parts)[0];
-                names.insert(id, name.to_string());
+                let parts: Vec<String> = split_name_parts(&name_parts)
+                    .into_iter()
+                    .map(|part| part.trim().to_owned())
+                    .collect();
+                if !parts.is_empty() {
+                    names.insert(id, parts);
+                }
             }
         }
     }
     Ok(names)
 }
 
-fn load_aircraft() -> Result<HashMap<String, Unit>, UnitDbError> {
-    Ok(HashMap::new())
-}
-
 /// Sea Power encodes unit information in the filename, usually structured
 /// like <nation>_<vessel_name>
 fn path_to_id(path: &Path) -> Option<&str> {
     path.file_stem().and_then(|p| p.to_str())
 }
 
+/// Split a Sea Power id structured like `<nation>_<hull>_<class>[_...]`,
+/// e.g. `usn_dd_spruance`, into its nation, hull and class. Both the class
+/// and the hull class of a unit are derived from this, so they agree.
+fn split_id(id: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = id.split('_');
+    Some((parts.next()?, parts.next()?, parts.next()?))
+}
+
 /// load a unit ini file
-fn load_ini(path: &Path) -> Result<Ini, UnitDbError> {
+fn load_ini(source: &dyn FileSource, path: &Path) -> Result<Ini, UnitDbError> {
     let mut config = Ini::new();
 
-    match config.load(path) {
+    match config.read(source.read_to_string(path)?) {
         Ok(_) => Ok(config),
         Err(reason) => Err(UnitDbError::IniParse {
             file: path.to_owned(),
@@ -263,23 +736,161 @@ mod tests {
     use super::*;
     use std::path::Path;
 
+    fn load_fixture(cache: &mut Cache) -> (UnitDb, LoadReport) {
+        UnitDb::from_source(&fixture::source(), Path::new(fixture::ROOT), cache).unwrap()
+    }
+
+    #[test]
+    fn test_load_fixture() {
+        let mut cache = Cache::new(Path::new(fixture::ROOT));
+        let (unit_db, report) = load_fixture(&mut cache);
+
+        assert_eq!(unit_db.languages(), ["en", "ru"]);
+        assert_eq!(unit_db.nations().len(), 2);
+        assert_eq!(unit_db.all().len(), 6);
+
+        let spruance = unit_db.by_id("usn_dd_spruance").unwrap();
+        assert_eq!(spruance.name, "Spruance");
+        assert_eq!(spruance.hull_names, ["USS Spruance", "USS Paul F. Foster"]);
+        assert_eq!(spruance.class_id, "usn_spruance");
+        assert_eq!(spruance.nation.name, "United States");
+        assert_eq!(spruance.metadata.max_speed, Some(32.5));
+        assert_eq!(spruance.metadata.service.introduced, Some(1975));
+        // the user's variants replace the original
+        assert_eq!(spruance.variants, ["AAW", "ASW"]);
+        assert_eq!(spruance.source, UnitSource::Original);
+
+        let kashin = unit_db.by_id("wp_bpk_kashin").unwrap();
+        assert_eq!(kashin.name_in("ru"), "Кашин");
+        assert_eq!(kashin.metadata.max_speed, Some(40.0));
+        assert_eq!(kashin.source, UnitSource::Mod("better_kashin".into()));
+
+        let utype = |id| unit_db.by_id(id).map(|u| u.utype);
+        assert_eq!(utype("wp_ss_foxtrot"), Some(UnitType::Submarine));
+        assert_eq!(utype("usn_f14a"), Some(UnitType::Aircraft));
+        assert_eq!(utype("usn_sh3h"), Some(UnitType::Helicopter));
+        assert_eq!(utype("wp_sam_sa2"), Some(UnitType::LandUnit));
+
+        let mut skipped: Vec<_> = report.skipped.iter().map(|s| &s.reason).collect();
+        skipped.sort_by_key(|r| r.to_string());
+        assert_eq!(
+            skipped,
+            [
+                &SkipReason::MissingName,
+                &SkipReason::UnknownNation("xyz".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_fixture_cached() {
+        let mut cache = Cache::new(Path::new(fixture::ROOT));
+        load_fixture(&mut cache);
+        let misses = cache.misses();
+        assert!(misses > 0);
+
+        // nothing has changed, so nothing should be read again
+        let (unit_db, _) = load_fixture(&mut cache);
+        assert_eq!(cache.misses(), misses);
+        assert_eq!(unit_db.all().len(), 6);
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let unit_db = UnitDb::fixture();
+        let ids = |query| -> Vec<&str> {
+            let units = unit_db.fuzzy_search(query);
+            units.into_iter().map(|u| u.id.as_str()).collect()
+        };
+        assert_eq!(ids("kash"), ["wp_bpk_kashin"]);
+        assert_eq!(ids("tomcat"), ["usn_f14a"]);
+        // everything matches an empty query, ordered by id
+        assert_eq!(
+            ids(""),
+            [
+                "usn_dd_spruance",
+                "usn_f14a",
+                "usn_sh3h",
+                "wp_bpk_kashin",
+                "wp_sam_sa2",
+                "wp_ss_foxtrot"
+            ]
+        );
+    }
+
     #[test]
     fn test_path_to_id() {
         let path = Path::new("/home/user/civ_ms_bulk.ini");
-        assert_eq!(path_to_id(&path), Some("civ_ms_bulk"));
+        assert_eq!(path_to_id(path), Some("civ_ms_bulk"));
 
         let path = Path::new("/home/user/civ_ms_bulk");
-        assert_eq!(path_to_id(&path), Some("civ_ms_bulk"));
+        assert_eq!(path_to_id(path), Some("civ_ms_bulk"));
 
         // FIXME: doesn't treat dir paths properly
         // let path = Path::new("/home/user/");
-        // assert!(path_to_id(&path).is_none());
+        // assert!(path_to_id(path).is_none());
 
         let path = Path::new("/");
-        assert!(path_to_id(&path).is_none());
+        assert!(path_to_id(path).is_none());
 
         let path = Path::new("civ_ms_bulk.ini");
-        assert_eq!(path_to_id(&path), Some("civ_ms_bulk"));
+        assert_eq!(path_to_id(path), Some("civ_ms_bulk"));
+    }
+
+    #[test]
+    fn test_is_variant_ignored() {
+        assert!(is_variant_ignored("General"));
+        assert!(is_variant_ignored("default"));
+        assert!(!is_variant_ignored("ASW"));
+    }
+
+    #[test]
+    fn test_unit_name_in() {
+        let unit = Unit {
+            id: "wp_bpk_kashin".into(),
+            name: "Kashin".into(),
+            localised_names: HashMap::from([("ru".into(), "Кашин".into())]),
+            hull_names: Vec::new(),
+            class_id: "wp_kashin".into(),
+            nation: Nation {
+                id: "wp".into(),
+                name: "Warsaw Pact".into(),
+            },
+            utype: UnitType::Vessel,
+            variants: Vec::new(),
+            metadata: UnitMetadata::default(),
+            source: UnitSource::Original,
+        };
+        assert_eq!(unit.name_in("ru"), "Кашин");
+        assert_eq!(unit.name_in("en"), "Kashin");
+        // missing translations fall back to english
+        assert_eq!(unit.name_in("de"), "Kashin");
+        assert_eq!(unit.localised("ru").name, "Кашин");
+    }
+
+    #[test]
+    fn test_unit_type() {
+        let land = UnitType::try_from("LandUnit".to_owned()).unwrap();
+        assert_eq!(land, UnitType::LandUnit);
+        assert_eq!(land.capitalised_singular(), "LandUnit");
+        assert_eq!(land.calitalised_plural(), "LandUnits");
+        assert_eq!(land.to_string(), "Land Unit");
+        assert_eq!(UnitType::Aircraft.calitalised_plural(), "Aircraft");
+        assert!(UnitType::try_from("Spaceship".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_class_id_of() {
+        assert_eq!(Unit::class_id_of("wp_bpk_kashin"), "wp_kashin");
+        assert_eq!(Unit::class_id_of("wp_bpk_kashin_mod"), "wp_kashin");
+        assert_eq!(Unit::class_id_of("usn_f14a"), "usn_f14a");
+    }
+
+    #[test]
+    fn test_split_id() {
+        assert_eq!(split_id("wp_bpk_kashin"), Some(("wp", "bpk", "kashin")));
+        assert_eq!(split_id("wp_bpk_kashin_mod"), Some(("wp", "bpk", "kashin")));
+        assert_eq!(split_id("usn_f14a"), None);
     }
 
     #[test]
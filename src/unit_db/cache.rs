@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use configparser::ini::{Ini, IniDefault};
+use thiserror::Error;
+
+use super::{FileSource, Nation, ServiceYears, UnitFile, UnitMetadata};
+
+/// Bump whenever the layout of a cached file changes, older caches are then
+/// discarded.
+const CACHE_VERSION: &str = "5";
+const HEADER_SECTION: &str = "__cache";
+const STAMP_KEY: &str = "__stamp";
+/// Separator for list values, e.g. sensors.
+const LIST_SEPARATOR: char = '|';
+
+/// The cached values of a single file.
+type Entry = HashMap<String, String>;
+
+/// Something that can be built from a game file and stored in the `Cache`.
+pub trait Cacheable: Sized {
+    fn to_entry(&self) -> Entry;
+    /// Returns `None` if the entry is incomplete, in which case the file
+    /// will be re-read.
+    fn from_entry(entry: &Entry) -> Option<Self>;
+}
+
+#[derive(Error, Debug)]
+#[error("failed to write unit cache {path}: {source}")]
+pub struct CacheWriteError {
+    path: PathBuf,
+    #[source]
+    source: io::Error,
+}
+
+#[derive(Clone, Debug)]
+struct CachedFile {
+    /// the modification time and size of the file when it was read
+    stamp: String,
+    values: Entry,
+}
+
+/// An on-disk cache of everything the `UnitDb` reads from game files.
+///
+/// Each file is cached individually and is only re-read if its modification
+/// time or size change, so that only changed files are re-read. The whole
+/// cache is discarded if the game directory changes.
+#[derive(Debug)]
+pub struct Cache {
+    game_root: PathBuf,
+    /// map of file path => cached contents
+    files: HashMap<String, CachedFile>,
+    /// every file fetched since the cache was loaded, anything else is stale
+    /// (e.g. the file was removed) and isn't saved.
+    fetched: HashSet<String>,
+    /// the number of files that had to be re-read
+    misses: usize,
+}
+
+impl Cache {
+    /// Create an empty cache for the game in `game_root`.
+    pub fn new(game_root: &Path) -> Self {
+        Self {
+            game_root: game_root.to_owned(),
+            files: HashMap::new(),
+            fetched: HashSet::new(),
+            misses: 0,
+        }
+    }
+
+    /// Load the cache from `path`, falling back to an empty cache if it is
+    /// missing, unreadable or built for another game directory.
+    pub fn load(path: &Path, game_root: &Path) -> Self {
+        let mut cache = Self::new(game_root);
+        let mut config = cache_ini();
+        let map = match config.load(path) {
+            Ok(map) => map,
+            Err(_) => return cache,
+        };
+
+        let header = map.get(HEADER_SECTION);
+        let value = |key: &str| header.and_then(|h| h.get(key)).cloned().flatten();
+        let game_root = game_root.to_string_lossy();
+        if value("version").as_deref() != Some(CACHE_VERSION)
+            || value("game_root").as_deref() != Some(&game_root)
+        {
+            return cache;
+        }
+
+        for (file, values) in map {
+            let mut values: Entry = values
+                .into_iter()
+                .map(|(k, v)| (k, v.unwrap_or_default()))
+                .collect();
+            if let Some(stamp) = values.remove(STAMP_KEY) {
+                cache.files.insert(file, CachedFile { stamp, values });
+            }
+        }
+        cache
+    }
+
+    /// Save all files fetched since loading the cache to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), CacheWriteError> {
+        let mut config = cache_ini();
+        let game_root = self.game_root.to_string_lossy();
+        config.set(HEADER_SECTION, "version", Some(CACHE_VERSION.into()));
+        config.set(HEADER_SECTION, "game_root", Some(game_root.into()));
+
+        let fetched = self.files.iter().filter(|(f, _)| self.fetched.contains(*f));
+        for (file, cached) in fetched {
+            config.set(file, STAMP_KEY, Some(cached.stamp.clone()));
+            for (key, value) in &cached.values {
+                config.set(file, key, Some(value.clone()));
+            }
+        }
+
+        let write = || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            config.write(path)
+        };
+        write().map_err(|source| CacheWriteError {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Return the cached value of the file at `path`, calling `load` to read
+    /// it if the file has changed in the `source` or isn't cached.
+    pub fn fetch<T, E, F>(&mut self, source: &dyn FileSource, path: &Path, load: F) -> Result<T, E>
+    where
+        T: Cacheable,
+        F: FnOnce() -> Result<T, E>,
+    {
+        let file = path.to_string_lossy().into_owned();
+        let stamp = source.stamp(path);
+        self.fetched.insert(file.clone());
+
+        let cached = self
+            .files
+            .get(&file)
+            .filter(|cached| Some(&cached.stamp) == stamp.as_ref())
+            .and_then(|cached| T::from_entry(&cached.values));
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        self.misses += 1;
+        let value = load()?;
+        // files we can't stamp can't be invalidated, so never cache them
+        if let Some(stamp) = stamp {
+            let values = value.to_entry();
+            self.files.insert(file, CachedFile { stamp, values });
+        }
+        Ok(value)
+    }
+
+    /// The number of files that were read instead of fetched from the cache.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+/// An ini parser that stores values verbatim, game files may contain
+/// characters that would otherwise be treated as comments or delimiters.
+fn cache_ini() -> Ini {
+    let mut defaults = IniDefault::default();
+    defaults.case_sensitive = true;
+    defaults.comment_symbols = vec![];
+    defaults.inline_comment_symbols = Some(vec![]);
+    defaults.delimiters = vec!['='];
+    Ini::new_from_defaults(defaults)
+}
+
+fn join_list(values: &[String]) -> String {
+    values.join(&LIST_SEPARATOR.to_string())
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(LIST_SEPARATOR)
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Unit names, keyed by unit id.
+impl Cacheable for HashMap<String, Vec<String>> {
+    fn to_entry(&self) -> Entry {
+        self.iter()
+            .map(|(id, names)| (id.clone(), join_list(names)))
+            .collect()
+    }
+
+    fn from_entry(entry: &Entry) -> Option<Self> {
+        let names = entry
+            .iter()
+            .map(|(id, names)| (id.clone(), split_list(names)))
+            .collect();
+        Some(names)
+    }
+}
+
+/// Nations, keyed by nation id.
+impl Cacheable for HashMap<String, Nation> {
+    fn to_entry(&self) -> Entry {
+        self.iter()
+            .map(|(id, nation)| (id.clone(), nation.name.clone()))
+            .collect()
+    }
+
+    fn from_entry(entry: &Entry) -> Option<Self> {
+        let nations = entry
+            .iter()
+            .map(|(id, name)| {
+                let nation = Nation {
+                    id: id.clone(),
+                    name: name.clone(),
+                };
+                (id.clone(), nation)
+            })
+            .collect();
+        Some(nations)
+    }
+}
+
+/// Variant references.
+impl Cacheable for Vec<String> {
+    fn to_entry(&self) -> Entry {
+        HashMap::from([("variants".into(), join_list(self))])
+    }
+
+    fn from_entry(entry: &Entry) -> Option<Self> {
+        entry.get("variants").map(|v| split_list(v))
+    }
+}
+
+impl Cacheable for UnitFile {
+    fn to_entry(&self) -> Entry {
+        let metadata = &self.metadata;
+        let mut entry = HashMap::from([
+            ("sensors".to_owned(), join_list(&metadata.sensors)),
+            ("sonars".to_owned(), join_list(&metadata.sonars)),
+            ("weapons".to_owned(), join_list(&metadata.weapons)),
+            ("loadouts".to_owned(), join_list(&metadata.loadouts)),
+        ]);
+        let optional = [
+            ("utype", self.utype.clone()),
+            ("max_speed", metadata.max_speed.map(|v| v.to_string())),
+            ("displacement", metadata.displacement.map(|v| v.to_string())),
+            ("hull_class", metadata.hull_class.clone()),
+            ("crew", metadata.crew.map(|v| v.to_string())),
+            (
+                "in_service",
+                metadata.service.introduced.map(|v| v.to_string()),
+            ),
+            ("retired", metadata.service.retired.map(|v| v.to_string())),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                entry.insert(key.to_owned(), value);
+            }
+        }
+        entry
+    }
+
+    fn from_entry(entry: &Entry) -> Option<Self> {
+        let get = |key: &str| entry.get(key).cloned();
+        Some(UnitFile {
+            utype: get("utype"),
+            metadata: UnitMetadata {
+                max_speed: get("max_speed").and_then(|v| v.parse().ok()),
+                displacement: get("displacement").and_then(|v| v.parse().ok()),
+                hull_class: get("hull_class"),
+                crew: get("crew").and_then(|v| v.parse().ok()),
+                sensors: split_list(entry.get("sensors")?),
+                sonars: split_list(entry.get("sonars")?),
+                weapons: split_list(entry.get("weapons")?),
+                loadouts: split_list(entry.get("loadouts")?),
+                service: ServiceYears {
+                    introduced: get("in_service").and_then(|v| v.parse().ok()),
+                    retired: get("retired").and_then(|v| v.parse().ok()),
+                },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_db::FsSource;
+
+    fn roundtrip<T: Cacheable>(value: &T) -> Option<T> {
+        T::from_entry(&value.to_entry())
+    }
+
+    /// A temporary directory unique to the process and test, removed when
+    /// dropped, even if the test fails.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("spmg_{}_{test}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_unit_file_roundtrip() {
+        let file = UnitFile {
+            utype: Some("Vessel".into()),
+            metadata: UnitMetadata {
+                max_speed: Some(38.5),
+                displacement: Some(4390),
+                hull_class: Some("bpk".into()),
+                crew: None,
+                sensors: vec!["MR-310 Angara".into(), "MG-312 Titan".into()],
+                sonars: vec!["MG-312 Titan".into()],
+                weapons: vec![],
+                loadouts: vec!["Strike".into()],
+                service: ServiceYears {
+                    introduced: Some(1962),
+                    retired: None,
+                },
+            },
+        };
+        let cached = roundtrip(&file).unwrap();
+        assert_eq!(cached.utype, file.utype);
+        assert_eq!(cached.metadata, file.metadata);
+    }
+
+    #[test]
+    fn test_variants_roundtrip() {
+        let variants = vec!["ASW".to_owned(), "AAW".to_owned()];
+        assert_eq!(roundtrip(&variants), Some(variants));
+        assert_eq!(roundtrip(&Vec::<String>::new()), Some(vec![]));
+    }
+
+    #[test]
+    fn test_fetch_only_reads_changed_files() {
+        let test_dir = TestDir::new("fetch_only_reads_changed_files");
+        let dir = &test_dir.0;
+        let file = dir.join("unit.ini");
+        let cache_file = dir.join("cache.ini");
+        fs::write(&file, "[General]").unwrap();
+
+        let load = || Ok::<_, ()>(vec!["ASW".to_owned()]);
+        let mut cache = Cache::new(dir);
+        assert_eq!(
+            cache.fetch(&FsSource, &file, load),
+            Ok(vec!["ASW".to_owned()])
+        );
+        assert_eq!(cache.misses(), 1);
+        cache.save(&cache_file).unwrap();
+
+        // unchanged file, shouldn't be loaded
+        let mut cache = Cache::load(&cache_file, dir);
+        let result = cache.fetch(&FsSource, &file, || Err(()));
+        assert_eq!(result, Ok(vec!["ASW".to_owned()]));
+        assert_eq!(cache.misses(), 0);
+
+        // different game directory invalidates everything
+        let mut cache = Cache::load(&cache_file, Path::new("elsewhere"));
+        assert_eq!(
+            cache.fetch::<Vec<String>, _, _>(&FsSource, &file, || Err(())),
+            Err(())
+        );
+    }
+}
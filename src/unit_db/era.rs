@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use configparser::ini::Ini;
+
+use super::{load_ini, metadata::parse_number, FsSource, UnitDbError};
+
+/// The years a unit was in service, either bound may be unknown.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServiceYears {
+    /// the year the unit entered service
+    pub introduced: Option<u16>,
+    /// the last year the unit was in service
+    pub retired: Option<u16>,
+}
+
+impl ServiceYears {
+    /// Parse the service years from `General.InService` and
+    /// `General.Retired` of a unit ini.
+    ///
+    /// Expects `config` to have been loaded case insensitively.
+    pub fn from_ini(config: &Ini) -> Self {
+        Self::from_section(config, "general")
+    }
+
+    fn from_section(config: &Ini, section: &str) -> Self {
+        let get = |key: &str| config.get(section, key).and_then(|s| parse_number(&s));
+        Self {
+            introduced: get("inservice"),
+            retired: get("retired"),
+        }
+    }
+
+    /// Whether the unit was in service during `year`, unknown bounds are
+    /// assumed to always be in service.
+    pub fn contains(&self, year: u16) -> bool {
+        self.introduced.map(|y| y <= year).unwrap_or(true)
+            && self.retired.map(|y| year <= y).unwrap_or(true)
+    }
+
+    /// Replace any bound that is known in `other`.
+    fn overlay(&mut self, other: Self) {
+        self.introduced = other.introduced.or(self.introduced);
+        self.retired = other.retired.or(self.retired);
+    }
+}
+
+impl std::fmt::Display for ServiceYears {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.introduced, self.retired) {
+            (None, None) => write!(f, "Unknown"),
+            (Some(introduced), None) => write!(f, "{introduced}-"),
+            (None, Some(retired)) => write!(f, "?-{retired}"),
+            (Some(introduced), Some(retired)) => write!(f, "{introduced}-{retired}"),
+        }
+    }
+}
+
+/// Service years keyed by unit id, replacing those from the game data.
+#[derive(Clone, Debug, Default)]
+pub struct EraOverrides {
+    years: HashMap<String, ServiceYears>,
+}
+
+impl EraOverrides {
+    /// Load the user's overrides from `path`, a missing file has no
+    /// overrides.
+    ///
+    /// Each section is a unit id with either bound, e.g.
+    ///
+    /// ```ini
+    /// [usn_dd_spruance]
+    /// InService=1975
+    /// Retired=2005
+    /// ```
+    pub fn load(path: &Path) -> Result<Self, UnitDbError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(Self::from_ini(&load_ini(&FsSource, path)?))
+    }
+
+    fn from_ini(config: &Ini) -> Self {
+        let years = config
+            .sections()
+            .into_iter()
+            .map(|id| {
+                let years = ServiceYears::from_section(config, &id);
+                (id, years)
+            })
+            .collect();
+        Self { years }
+    }
+
+    /// Apply any override for the unit `id` on top of `years`.
+    pub fn apply(&self, id: &str, years: &mut ServiceYears) {
+        // sections are lowercased when loaded
+        if let Some(overridden) = self.years.get(&id.to_lowercase()) {
+            years.overlay(*overridden);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn years(introduced: Option<u16>, retired: Option<u16>) -> ServiceYears {
+        ServiceYears {
+            introduced,
+            retired,
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let spruance = years(Some(1975), Some(2005));
+        assert!(spruance.contains(1975));
+        assert!(spruance.contains(1985));
+        assert!(spruance.contains(2005));
+        assert!(!spruance.contains(1974));
+        assert!(!spruance.contains(2006));
+        assert!(years(None, None).contains(1900));
+        assert!(years(Some(1975), None).contains(2030));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut config = Ini::new();
+        config
+            .read("[USN_DD_Spruance]\nRetired=2000".into())
+            .unwrap();
+        let overrides = EraOverrides::from_ini(&config);
+
+        let mut spruance = years(Some(1975), Some(2005));
+        overrides.apply("usn_dd_spruance", &mut spruance);
+        assert_eq!(spruance, years(Some(1975), Some(2000)));
+
+        let mut kidd = years(Some(1981), None);
+        overrides.apply("usn_ddg_kidd", &mut kidd);
+        assert_eq!(kidd, years(Some(1981), None));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(years(Some(1975), Some(2005)).to_string(), "1975-2005");
+        assert_eq!(years(Some(1975), None).to_string(), "1975-");
+        assert_eq!(years(None, Some(2005)).to_string(), "?-2005");
+        assert_eq!(years(None, None).to_string(), "Unknown");
+    }
+}
@@ -0,0 +1,142 @@
+//! A small game install held in memory, for testing the loader without Sea
+//! Power installed.
+//!
+//! The unit inis are written by hand, not copied from the game, so their
+//! metadata keys are as unconfirmed as those `UnitMetadata` reads.
+
+use std::path::{Path, PathBuf};
+
+use super::source::MemorySource;
+use crate::dir;
+
+pub const ROOT: &str = "sea_power";
+
+const NATIONS: &str = "
+[Nation1]
+NationPrefix=usn
+NationName=United States
+[Nation2]
+NationPrefix=wp
+NationName=Soviet Union
+";
+
+const VESSEL_NAMES: &str = "
+[usn_dd_spruance]
+Default=Spruance,USS Spruance,USS Paul F. Foster
+[wp_bpk_kashin]
+Default=Kashin,Komsomolets Ukrainy,Smetlivy
+[wp_ss_foxtrot]
+Default=Foxtrot
+[xyz_dd_unknown]
+Default=Unknown
+";
+
+const VESSEL_NAMES_RU: &str = "
+[wp_bpk_kashin]
+Default=Кашин,Комсомолец Украины
+";
+
+const AIRCRAFT_NAMES: &str = "
+[usn_f14a]
+Default=F-14A Tomcat
+[usn_sh3h]
+Default=SH-3H Sea King
+";
+
+const LAND_UNIT_NAMES: &str = "
+[wp_sam_sa2]
+Default=SA-2 Guideline
+";
+
+const SPRUANCE: &str = "
+[General]
+UnitType=Vessel
+MaxSpeed=32.5
+Displacement=8040
+InService=1975
+[Sonar1]
+Name=AN/SQS-53
+";
+
+const KASHIN: &str = "
+[General]
+UnitType=Vessel
+MaxSpeed=38
+Displacement=4390
+";
+
+const KASHIN_MOD: &str = "
+[General]
+UnitType=Vessel
+MaxSpeed=40
+Displacement=4390
+";
+
+const TOMCAT: &str = "
+[General]
+UnitType=Aircraft
+[Loadout1]
+Name=Fleet Defence
+";
+
+fn original(path: &str) -> PathBuf {
+    dir::original_dir(Path::new(ROOT)).join(path)
+}
+
+fn user(path: &str) -> PathBuf {
+    dir::user_dir(Path::new(ROOT)).join(path)
+}
+
+fn mod_file(name: &str, path: &str) -> PathBuf {
+    dir::mods_dir(Path::new(ROOT)).join(name).join(path)
+}
+
+/// The fixture install, with a single mod overriding the Kashin and the user
+/// overriding the variants of the Spruance.
+///
+/// `xyz_dd_unknown` (unknown nation) and `usn_ss_nameless` (no name) are
+/// both skipped when loaded.
+pub fn source() -> MemorySource {
+    MemorySource::new()
+        .file(original("nations_reference.ini"), NATIONS)
+        .file(original("language_en/vessel_names.ini"), VESSEL_NAMES)
+        .file(original("language_ru/vessel_names.ini"), VESSEL_NAMES_RU)
+        .file(original("language_en/aircraft_names.ini"), AIRCRAFT_NAMES)
+        .file(original("language_en/land_unit_names.ini"), LAND_UNIT_NAMES)
+        .file(original("vessels/usn_dd_spruance.ini"), SPRUANCE)
+        .file(
+            original("vessels/usn_dd_spruance_variants.ini"),
+            "[General]\n[ASW]",
+        )
+        .file(original("vessels/wp_bpk_kashin.ini"), KASHIN)
+        .file(
+            original("vessels/wp_ss_foxtrot.ini"),
+            "[General]\nUnitType=Submarine",
+        )
+        .file(
+            original("vessels/xyz_dd_unknown.ini"),
+            "[General]\nUnitType=Vessel",
+        )
+        .file(
+            original("vessels/usn_ss_nameless.ini"),
+            "[General]\nUnitType=Submarine",
+        )
+        .file(original("aircraft/usn_f14a.ini"), TOMCAT)
+        .file(
+            original("aircraft/usn_f14a_variants.ini"),
+            "[General]\n[Late]",
+        )
+        .file(
+            original("aircraft/usn_sh3h.ini"),
+            "[General]\nUnitType=Helicopter",
+        )
+        .file(
+            original("land_units/wp_sam_sa2.ini"),
+            "[General]\nUnitType=LandUnit",
+        )
+        .file(
+            mod_file("better_kashin", "vessels/wp_bpk_kashin.ini"),
+            KASHIN_MOD,
+        )
+        .file(user("vessels/usn_dd_spruance_variants.ini"), "[AAW]\n[ASW]")
+}
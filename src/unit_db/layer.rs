@@ -0,0 +1,79 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::FileSource;
+use crate::dir;
+
+/// Where a unit was loaded from.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnitSource {
+    /// The data shipped with the game.
+    Original,
+    /// A mod, identified by its directory name.
+    Mod(String),
+    /// The player's own `user` directory.
+    User,
+}
+
+impl std::fmt::Display for UnitSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Original => write!(f, "Original"),
+            Self::Mod(name) => write!(f, "{name}"),
+            Self::User => write!(f, "User"),
+        }
+    }
+}
+
+/// A directory structured like the game's `original` data directory, whose
+/// entries are overlaid on top of the layers before it.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub source: UnitSource,
+    pub data_dir: PathBuf,
+}
+
+impl Layer {
+    pub fn is_original(&self) -> bool {
+        self.source == UnitSource::Original
+    }
+}
+
+/// Find every data layer in the game directory, ordered from lowest to
+/// highest precedence.
+///
+/// The original data always comes first, followed by any mods (in
+/// alphabetical order) and finally the user directory, so that the player
+/// always has the last word.
+pub fn find_layers(source: &dyn FileSource, root_dir: &Path) -> io::Result<Vec<Layer>> {
+    let mut layers = vec![Layer {
+        source: UnitSource::Original,
+        data_dir: dir::original_dir(root_dir),
+    }];
+
+    let mods_dir = dir::mods_dir(root_dir);
+    if source.is_dir(&mods_dir) {
+        let mut mods = Vec::new();
+        for path in source.read_dir(&mods_dir)? {
+            let name = path.file_name().and_then(|name| name.to_str());
+            if let Some(name) = name.filter(|_| source.is_dir(&path)) {
+                mods.push(Layer {
+                    source: UnitSource::Mod(name.to_owned()),
+                    data_dir: path.clone(),
+                });
+            }
+        }
+        mods.sort_by(|a, b| a.source.cmp(&b.source));
+        layers.extend(mods);
+    }
+
+    let user_dir = dir::user_dir(root_dir);
+    if source.is_dir(&user_dir) {
+        layers.push(Layer {
+            source: UnitSource::User,
+            data_dir: user_dir,
+        });
+    }
+
+    Ok(layers)
+}
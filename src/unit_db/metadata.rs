@@ -0,0 +1,237 @@
+use configparser::ini::Ini;
+
+use super::era::ServiceYears;
+
+// None of the keys or sections read here are confirmed by a game file in this
+// repo, they are what unit inis are assumed to contain. Anything missing is
+// left as `None` or empty, so units still load without them.
+
+/// Sections within a unit ini whose name (minus any trailing index, e.g.
+/// `Radar2`) describes a sensor.
+const SENSOR_SECTIONS: [&str; 7] = ["radar", "sonar", "towedarray", "esm", "ecm", "irst", "mad"];
+/// The subset of `SENSOR_SECTIONS` that listen underwater.
+const SONAR_SECTIONS: [&str; 2] = ["sonar", "towedarray"];
+/// Same as `SENSOR_SECTIONS`, but for weapon systems.
+const WEAPON_SECTIONS: [&str; 4] = ["weapon", "weaponsystem", "launcher", "gun"];
+/// Same as `SENSOR_SECTIONS`, but for the named loadouts a unit can carry,
+/// e.g. an aircraft's strike or air superiority fit.
+const LOADOUT_SECTIONS: [&str; 1] = ["loadout"];
+
+/// A rough size classification of a unit, based off its displacement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SizeClass {
+    /// patrol boats, corvettes and small auxiliaries
+    Small,
+    /// frigates and destroyers
+    Medium,
+    /// cruisers and large auxiliaries
+    Large,
+    /// carriers, battleships and the largest merchants
+    Capital,
+}
+
+impl SizeClass {
+    pub fn all() -> [Self; 4] {
+        use SizeClass::*;
+        [Small, Medium, Large, Capital]
+    }
+
+    fn from_displacement(tonnes: u32) -> Self {
+        match tonnes {
+            0..1_500 => Self::Small,
+            1_500..7_000 => Self::Medium,
+            7_000..25_000 => Self::Large,
+            _ => Self::Capital,
+        }
+    }
+}
+
+impl std::fmt::Display for SizeClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Small => "Small",
+            Self::Medium => "Medium",
+            Self::Large => "Large",
+            Self::Capital => "Capital",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// Performance and role information parsed from a unit ini.
+///
+/// Every field is optional, since not all units (especially modded ones)
+/// define them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnitMetadata {
+    /// maximum speed in knots
+    pub max_speed: Option<f32>,
+    /// full load displacement in tonnes
+    pub displacement: Option<u32>,
+    /// the hull designation, e.g. `dd` or `bpk`
+    pub hull_class: Option<String>,
+    pub crew: Option<u32>,
+    /// names of all fitted sensors
+    pub sensors: Vec<String>,
+    /// names of the fitted sensors that are sonars, also in `sensors`
+    pub sonars: Vec<String>,
+    /// names of all fitted weapon systems
+    pub weapons: Vec<String>,
+    /// names of the loadouts the unit can be given in a mission
+    pub loadouts: Vec<String>,
+    pub service: ServiceYears,
+}
+
+impl UnitMetadata {
+    /// Parse metadata from an already loaded unit ini for the unit `id`.
+    ///
+    /// Expects `config` to have been loaded case insensitively. The `General`
+    /// keys (`MaxSpeed`, `Displacement`, `Crew` and `HullClass`) are
+    /// unconfirmed, see the top of this file.
+    pub fn from_ini(id: &str, config: &Ini) -> Self {
+        let get = |key: &str| config.get("general", key);
+        Self {
+            max_speed: get("maxspeed").and_then(|s| parse_number(&s)),
+            displacement: get("displacement").and_then(|s| parse_number(&s)),
+            hull_class: get("hullclass")
+                .or_else(|| super::split_id(id).map(|(_, hull, _)| hull.to_owned()))
+                .map(|class| class.to_lowercase()),
+            crew: get("crew").and_then(|s| parse_number(&s)),
+            sensors: systems(config, &SENSOR_SECTIONS),
+            sonars: systems(config, &SONAR_SECTIONS),
+            weapons: systems(config, &WEAPON_SECTIONS),
+            loadouts: systems(config, &LOADOUT_SECTIONS),
+            service: ServiceYears::from_ini(config),
+        }
+    }
+
+    pub fn size_class(&self) -> Option<SizeClass> {
+        self.displacement.map(SizeClass::from_displacement)
+    }
+
+    /// Whether any sensor name contains `name`, case insensitively.
+    pub fn has_sensor(&self, name: &str) -> bool {
+        contains_ignore_case(&self.sensors, name)
+    }
+
+    /// Whether any weapon system name contains `name`, case insensitively.
+    pub fn has_weapon(&self, name: &str) -> bool {
+        contains_ignore_case(&self.weapons, name)
+    }
+}
+
+/// Collect the names of every system in a section matching one of `kinds`.
+fn systems(config: &Ini, kinds: &[&str]) -> Vec<String> {
+    let mut sections = config.sections();
+    sections.sort();
+    sections
+        .into_iter()
+        .filter(|section| kinds.contains(&section.trim_end_matches(|c: char| c.is_ascii_digit())))
+        .filter_map(|section| {
+            config
+                .get(&section, "name")
+                .or_else(|| config.get(&section, "type"))
+        })
+        .collect()
+}
+
+fn contains_ignore_case(names: &[String], needle: &str) -> bool {
+    let needle = needle.to_lowercase();
+    names.iter().any(|n| n.to_lowercase().contains(&needle))
+}
+
+/// Parse the leading number of a value, ignoring any trailing units, e.g.
+/// `32 knots` or `4,390 t`.
+///
+/// Commas are only allowed as thousands separators, anything else is
+/// rejected rather than misread, e.g. `4,39`.
+pub(super) fn parse_number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(value.len());
+    let mut groups = value[..end].split(',');
+    let mut number = groups.next()?.to_owned();
+    for group in groups {
+        let thousands = group.split('.').next().unwrap_or_default();
+        if number.contains('.') || thousands.len() != 3 {
+            return None;
+        }
+        number.push_str(group);
+    }
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KASHIN: &str = "
+[General]
+UnitType=Vessel
+MaxSpeed=38 knots
+Displacement=4390
+Crew=266
+InService=1962
+Retired=1991
+[Radar1]
+Name=MR-310 Angara
+[Sonar1]
+Name=MG-312 Titan
+[Weapon1]
+Name=SA-N-1 Goa
+[Weapon2]
+Type=AK-726
+[Engine1]
+Name=M-3
+[Loadout1]
+Name=Full War Load
+[Loadout2]
+Name=Training
+";
+
+    fn load(str: &str) -> Ini {
+        let mut config = Ini::new();
+        config.read(str.into()).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_from_ini() {
+        let metadata = UnitMetadata::from_ini("wp_bpk_kashin", &load(KASHIN));
+        assert_eq!(metadata.max_speed, Some(38.0));
+        assert_eq!(metadata.displacement, Some(4390));
+        assert_eq!(metadata.hull_class.as_deref(), Some("bpk"));
+        assert_eq!(metadata.crew, Some(266));
+        assert_eq!(metadata.sensors, vec!["MR-310 Angara", "MG-312 Titan"]);
+        assert_eq!(metadata.sonars, vec!["MG-312 Titan"]);
+        assert_eq!(metadata.weapons, vec!["SA-N-1 Goa", "AK-726"]);
+        assert_eq!(metadata.loadouts, vec!["Full War Load", "Training"]);
+        assert_eq!(metadata.size_class(), Some(SizeClass::Medium));
+        assert_eq!(metadata.service.to_string(), "1962-1991");
+        assert!(metadata.has_weapon("goa"));
+        assert!(!metadata.has_sensor("goa"));
+    }
+
+    #[test]
+    fn test_from_ini_missing() {
+        let metadata = UnitMetadata::from_ini("civ_tanker", &load("[General]\nUnitType=Vessel"));
+        assert_eq!(metadata.hull_class, None);
+        assert_eq!(metadata.size_class(), None);
+        assert!(metadata.sensors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number::<u32>("266"), Some(266));
+        assert_eq!(parse_number::<f32>(" 32.5 knots"), Some(32.5));
+        assert_eq!(parse_number::<u32>("unknown"), None);
+        assert_eq!(parse_number::<u32>("4,390"), Some(4390));
+        assert_eq!(parse_number::<u32>("1,234,567 t"), Some(1_234_567));
+        assert_eq!(parse_number::<f32>("1,234.5"), Some(1234.5));
+        // integers don't silently drop a fraction
+        assert_eq!(parse_number::<u32>("4390.5"), None);
+        assert_eq!(parse_number::<u32>("4,39"), None);
+        assert_eq!(parse_number::<f32>("1.5,000"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use super::{Nation, SizeClass, Unit, UnitType};
+
+/// A composable query over units.
+///
+/// Each criterion narrows the query further, where a criterion with several
+/// values (e.g. nations) matches a unit if any one of its values do. An empty
+/// query matches every unit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnitQuery {
+    pub nations: Vec<Nation>,
+    pub utypes: Vec<UnitType>,
+    /// glob patterns (`*` and `?`) matched against the unit id
+    pub id_patterns: Vec<String>,
+    /// case insensitive substring of the unit name, in any language
+    pub name: Option<String>,
+    pub excluded_ids: Vec<String>,
+    /// see `Unit::class_id`
+    pub class_ids: Vec<String>,
+    pub hull_classes: Vec<String>,
+    pub size_classes: Vec<SizeClass>,
+    /// minimum speed in knots
+    pub min_speed: Option<f32>,
+    /// the unit must carry every one of these sensors
+    pub sensors: Vec<String>,
+    /// the unit must carry every one of these weapon systems
+    pub weapons: Vec<String>,
+    /// the unit must have been in service during this year
+    pub in_service: Option<u16>,
+}
+
+impl UnitQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn nation(mut self, nation: Nation) -> Self {
+        self.nations.push(nation);
+        self
+    }
+
+    pub fn utype(mut self, utype: UnitType) -> Self {
+        self.utypes.push(utype);
+        self
+    }
+
+    pub fn id_pattern(mut self, pattern: &str) -> Self {
+        self.id_patterns.push(pattern.to_owned());
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub fn exclude(mut self, id: &str) -> Self {
+        self.excluded_ids.push(id.to_owned());
+        self
+    }
+
+    pub fn class(mut self, class_id: &str) -> Self {
+        self.class_ids.push(class_id.to_owned());
+        self
+    }
+
+    pub fn hull_class(mut self, hull_class: &str) -> Self {
+        self.hull_classes.push(hull_class.to_lowercase());
+        self
+    }
+
+    pub fn size_class(mut self, size_class: SizeClass) -> Self {
+        self.size_classes.push(size_class);
+        self
+    }
+
+    pub fn min_speed(mut self, knots: f32) -> Self {
+        self.min_speed = Some(knots);
+        self
+    }
+
+    pub fn sensor(mut self, sensor: &str) -> Self {
+        self.sensors.push(sensor.to_owned());
+        self
+    }
+
+    pub fn weapon(mut self, weapon: &str) -> Self {
+        self.weapons.push(weapon.to_owned());
+        self
+    }
+
+    pub fn in_service(mut self, year: u16) -> Self {
+        self.in_service = Some(year);
+        self
+    }
+
+    pub fn matches(&self, unit: &Unit) -> bool {
+        let metadata = &unit.metadata;
+        any_or_empty(&self.nations, |n| *n == unit.nation)
+            && any_or_empty(&self.utypes, |t| *t == unit.utype)
+            && any_or_empty(&self.id_patterns, |p| glob_match(p, &unit.id))
+            && self
+                .name
+                .as_ref()
+                .map(|name| {
+                    // the query may be typed in the UI language, while the
+                    // mission is generated from the english names
+                    let name = name.to_lowercase();
+                    std::iter::once(&unit.name)
+                        .chain(unit.localised_names.values())
+                        .any(|n| n.to_lowercase().contains(&name))
+                })
+                .unwrap_or(true)
+            && !self.excluded_ids.contains(&unit.id)
+            && any_or_empty(&self.class_ids, |c| *c == unit.class_id)
+            && any_or_empty(&self.hull_classes, |h| {
+                metadata.hull_class.as_ref() == Some(h)
+            })
+            && any_or_empty(&self.size_classes, |s| metadata.size_class() == Some(*s))
+            && self
+                .min_speed
+                .map(|min| metadata.max_speed.is_some_and(|speed| speed >= min))
+                .unwrap_or(true)
+            && self.sensors.iter().all(|s| metadata.has_sensor(s))
+            && self.weapons.iter().all(|w| metadata.has_weapon(w))
+            && self
+                .in_service
+                .map(|year| metadata.service.contains(year))
+                .unwrap_or(true)
+    }
+}
+
+/// A short human readable description, e.g. `Soviet Union Vessel`. Empty if
+/// the query matches everything.
+impl std::fmt::Display for UnitQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn join<T: ToString>(values: &[T]) -> Option<String> {
+            let strs: Vec<String> = values.iter().map(T::to_string).collect();
+            (!strs.is_empty()).then(|| strs.join("/"))
+        }
+
+        let with: Vec<String> = self.sensors.iter().chain(&self.weapons).cloned().collect();
+        let parts = [
+            join(&self.nations),
+            join(&self.size_classes),
+            join(&self.hull_classes).map(|h| h.to_uppercase()),
+            join(&self.utypes),
+            join(&self.class_ids).map(|c| format!("{c} class")),
+            join(&self.id_patterns),
+            self.name.as_ref().map(|n| format!("\"{n}\"")),
+            self.min_speed.map(|s| format!(">= {s}kn")),
+            join(&with).map(|w| format!("with {w}")),
+            self.in_service.map(|y| format!("in {y}")),
+            join(&self.excluded_ids).map(|e| format!("except {e}")),
+        ];
+        let parts: Vec<String> = parts.into_iter().flatten().collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+fn any_or_empty<T>(values: &[T], pred: impl Fn(&T) -> bool) -> bool {
+    values.is_empty() || values.iter().any(pred)
+}
+
+/// Match `text` against a glob `pattern`, where `*` matches any number of
+/// characters and `?` matches exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // index of the last `*` in pattern and the text position it matched from
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            // backtrack, letting the last star consume one more character
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_db::{ServiceYears, UnitMetadata, UnitSource};
+
+    fn kashin() -> Unit {
+        Unit {
+            id: "wp_bpk_kashin".into(),
+            name: "Kashin".into(),
+            localised_names: Default::default(),
+            hull_names: vec!["Smetlivy".into()],
+            class_id: "wp_kashin".into(),
+            nation: Nation {
+                id: "wp".into(),
+                name: "Soviet Union".into(),
+            },
+            utype: UnitType::Vessel,
+            variants: Vec::new(),
+            metadata: UnitMetadata {
+                max_speed: Some(38.0),
+                displacement: Some(4390),
+                hull_class: Some("bpk".into()),
+                crew: Some(266),
+                sensors: vec!["MR-310 Angara".into()],
+                sonars: vec![],
+                weapons: vec!["SA-N-1 Goa".into()],
+                loadouts: vec![],
+                service: ServiceYears {
+                    introduced: Some(1962),
+                    retired: Some(1991),
+                },
+            },
+            source: UnitSource::Original,
+        }
+    }
+
+    fn nation(id: &str) -> Nation {
+        Nation {
+            id: id.into(),
+            name: id.into(),
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let unit = kashin();
+        assert!(UnitQuery::new().matches(&unit));
+        assert!(UnitQuery::new()
+            .nation(nation("usn"))
+            .nation(nation("wp"))
+            .matches(&unit));
+        assert!(!UnitQuery::new().nation(nation("usn")).matches(&unit));
+        assert!(!UnitQuery::new().utype(UnitType::Submarine).matches(&unit));
+        assert!(UnitQuery::new().id_pattern("wp_*").matches(&unit));
+        assert!(UnitQuery::new().name("kash").matches(&unit));
+        let mut localised = unit.clone();
+        localised
+            .localised_names
+            .insert("ru".into(), "Кашин".into());
+        assert!(UnitQuery::new().name("кашин").matches(&localised));
+        assert!(UnitQuery::new()
+            .name("kash")
+            .matches(&localised.localised("ru")));
+        assert!(!UnitQuery::new().exclude("wp_bpk_kashin").matches(&unit));
+        assert!(UnitQuery::new().hull_class("BPK").matches(&unit));
+        assert!(UnitQuery::new().class("wp_kashin").matches(&unit));
+        assert!(!UnitQuery::new().class("wp_kresta").matches(&unit));
+        assert!(UnitQuery::new()
+            .size_class(SizeClass::Medium)
+            .matches(&unit));
+        assert!(UnitQuery::new().min_speed(30.0).matches(&unit));
+        assert!(!UnitQuery::new().min_speed(40.0).matches(&unit));
+        assert!(UnitQuery::new()
+            .sensor("angara")
+            .weapon("goa")
+            .matches(&unit));
+        assert!(!UnitQuery::new().weapon("harpoon").matches(&unit));
+        assert!(UnitQuery::new().in_service(1985).matches(&unit));
+        assert!(!UnitQuery::new().in_service(1995).matches(&unit));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(UnitQuery::new().to_string(), "");
+        let query = UnitQuery::new()
+            .nation(nation("wp"))
+            .utype(UnitType::Vessel)
+            .weapon("SAM");
+        assert_eq!(query.to_string(), "wp Vessel with SAM");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("usn_*", "usn_dd_spruance"));
+        assert!(glob_match("*_dd_*", "usn_dd_spruance"));
+        assert!(glob_match("usn_d?_*", "usn_dd_spruance"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("usn_*", "wp_bpk_kashin"));
+        assert!(!glob_match("usn_dd", "usn_dd_spruance"));
+        assert!(glob_match("*ance", "usn_dd_spruance"));
+    }
+}
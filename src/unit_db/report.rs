@@ -0,0 +1,93 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why a unit file was not loaded into the `UnitDb`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// the file name couldn't be turned into a unit id
+    InvalidFileName,
+    /// the unit id isn't prefixed with a nation, e.g. `usn_`
+    MissingNationPrefix,
+    /// the nation prefix isn't in any nation reference
+    UnknownNation(String),
+    /// no english name was found in the language files
+    MissingName,
+    /// the unit ini has no `General.UnitType`
+    MissingUnitType,
+    /// `General.UnitType` isn't a type we support
+    UnknownUnitType(String),
+    /// a variants file exists, but the unit it belongs to doesn't
+    VariantsWithoutUnit,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidFileName => write!(f, "invalid file name"),
+            Self::MissingNationPrefix => write!(f, "unit id has no nation prefix"),
+            Self::UnknownNation(nation) => write!(f, "unknown nation prefix `{nation}`"),
+            Self::MissingName => write!(f, "no english name in language files"),
+            Self::MissingUnitType => write!(f, "missing General.UnitType"),
+            Self::UnknownUnitType(utype) => write!(f, "unknown unit type `{utype}`"),
+            Self::VariantsWithoutUnit => write!(f, "variants for a unit that doesn't exist"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SkippedFile {
+    pub file: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// Everything that went wrong, but wasn't fatal, while loading the `UnitDb`.
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+    pub skipped: Vec<SkippedFile>,
+}
+
+impl LoadReport {
+    pub fn skip(&mut self, file: &Path, reason: SkipReason) {
+        self.skipped.push(SkippedFile {
+            file: file.to_owned(),
+            reason,
+        });
+    }
+
+    /// Render the report as text, one skipped file per line.
+    pub fn to_text(&self) -> String {
+        let mut skipped = self.skipped.clone();
+        skipped.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let mut text = format!("{} unit files skipped\n", skipped.len());
+        for SkippedFile { file, reason } in skipped {
+            let _ = writeln!(text, "{}: {reason}", file.display());
+        }
+        text
+    }
+
+    /// Write the full report to `path`.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let mut report = LoadReport::default();
+        report.skip(Path::new("b.ini"), SkipReason::MissingName);
+        report.skip(Path::new("a.ini"), SkipReason::UnknownNation("xyz".into()));
+        assert_eq!(
+            report.to_text(),
+            "2 unit files skipped\n\
+             a.ini: unknown nation prefix `xyz`\n\
+             b.ini: no english name in language files\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use super::Unit;
+
+// scores for how well a single query term matched a field, the best match
+// of each term is summed to rank a unit.
+const EXACT_SCORE: u32 = 100;
+const PREFIX_SCORE: u32 = 75;
+const SUBSTRING_SCORE: u32 = 50;
+/// The best possible score of a fuzzy (subsequence) match, reduced by the
+/// gaps between the matched characters.
+const FUZZY_SCORE: u32 = 30;
+
+/// Rank `units` by how well they match `query`, best matches first.
+///
+/// Every whitespace separated term of the query must fuzzy match the unit's
+/// name (in any language), hull names, id or nation, e.g. `kash` matches the Kashin and
+/// `usn ddg` matches every US Navy guided missile destroyer. An empty query
+/// matches everything and keeps the original order.
+pub fn fuzzy_search<'a, I>(units: I, query: &str) -> Vec<&'a Unit>
+where
+    I: IntoIterator<Item = &'a Unit>,
+{
+    let mut matches: Vec<(u32, &Unit)> = units
+        .into_iter()
+        .filter_map(|unit| Some((score(unit, query)?, unit)))
+        .collect();
+    // stable, so equal matches keep their original order
+    matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+    matches.into_iter().map(|(_, unit)| unit).collect()
+}
+
+/// Score how well `unit` matches `query`, `None` if it doesn't match at all.
+pub fn score(unit: &Unit, query: &str) -> Option<u32> {
+    let fields: Vec<String> = [&unit.name, &unit.id, &unit.nation.id, &unit.nation.name]
+        .into_iter()
+        .chain(unit.localised_names.values())
+        .chain(&unit.hull_names)
+        .map(|field| field.to_lowercase())
+        .collect();
+
+    query
+        .split_whitespace()
+        .map(|term| {
+            let term = term.to_lowercase();
+            fields
+                .iter()
+                .filter_map(|field| term_score(field, &term))
+                .max()
+        })
+        .sum()
+}
+
+fn term_score(field: &str, term: &str) -> Option<u32> {
+    // ids are separated by underscores, e.g. usn_ddg_kidd
+    let mut words = field.split(|c: char| c.is_whitespace() || c == '_' || c == '-');
+    if words.clone().any(|word| word == term) {
+        Some(EXACT_SCORE)
+    } else if words.any(|word| word.starts_with(term)) {
+        Some(PREFIX_SCORE)
+    } else if field.contains(term) {
+        Some(SUBSTRING_SCORE)
+    } else {
+        fuzzy_score(field, term)
+    }
+}
+
+/// Match `term` as a subsequence of `field`, penalising gaps between the
+/// matched characters.
+fn fuzzy_score(field: &str, term: &str) -> Option<u32> {
+    let mut chars = field.chars().enumerate();
+    let mut gaps = 0;
+    let mut last = None;
+    for c in term.chars() {
+        let (idx, _) = chars.find(|(_, f)| *f == c)?;
+        if let Some(last) = last {
+            gaps += idx - last - 1;
+        }
+        last = Some(idx);
+    }
+    let gaps = u32::try_from(gaps).unwrap_or(u32::MAX);
+    Some(FUZZY_SCORE.saturating_sub(gaps).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_db::{Nation, UnitMetadata, UnitSource, UnitType};
+
+    fn unit(id: &str, name: &str, nation: (&str, &str)) -> Unit {
+        Unit {
+            id: id.into(),
+            name: name.into(),
+            localised_names: Default::default(),
+            hull_names: Vec::new(),
+            class_id: Unit::class_id_of(id),
+            nation: Nation {
+                id: nation.0.into(),
+                name: nation.1.into(),
+            },
+            utype: UnitType::Vessel,
+            variants: Vec::new(),
+            metadata: UnitMetadata::default(),
+            source: UnitSource::Original,
+        }
+    }
+
+    fn ids(units: Vec<&Unit>) -> Vec<&str> {
+        units.into_iter().map(|u| u.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let mut units = [
+            unit("wp_bpk_kashin", "Kashin", ("wp", "Soviet Union")),
+            unit("usn_ddg_kidd", "Kidd", ("usn", "United States")),
+            unit("usn_dd_spruance", "Spruance", ("usn", "United States")),
+        ];
+        units[2].hull_names = vec!["USS Paul F. Foster".into()];
+        assert_eq!(ids(fuzzy_search(&units, "kash")), vec!["wp_bpk_kashin"]);
+        assert_eq!(ids(fuzzy_search(&units, "foster")), vec!["usn_dd_spruance"]);
+        assert_eq!(ids(fuzzy_search(&units, "usn ddg")), vec!["usn_ddg_kidd"]);
+        // an exact hull match ranks above a prefix match of `ddg`
+        assert_eq!(
+            ids(fuzzy_search(&units, "usn dd")),
+            vec!["usn_dd_spruance", "usn_ddg_kidd"]
+        );
+        assert_eq!(fuzzy_search(&units, "").len(), units.len());
+        assert!(fuzzy_search(&units, "zzz").is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("kashin", "ksn"), Some(FUZZY_SCORE - 3));
+        assert_eq!(fuzzy_score("kashin", "xyz"), None);
+        // out of order characters don't match
+        assert_eq!(fuzzy_score("kashin", "nk"), None);
+    }
+}
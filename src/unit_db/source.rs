@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Where the `UnitDb` reads game files from.
+///
+/// Every path is a full path, as if the game were installed on disk, so that
+/// the layout of the game directory is the same regardless of the source.
+pub trait FileSource {
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    /// The paths of every entry directly within `dir`.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    /// A value that changes whenever the file at `path` does, `None` if
+    /// changes can't be detected, in which case the file is never cached.
+    fn stamp(&self, path: &Path) -> Option<String>;
+}
+
+/// Reads files from the real filesystem.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FsSource;
+
+impl FileSource for FsSource {
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    /// The modification time and size of the file.
+    fn stamp(&self, path: &Path) -> Option<String> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!(
+            "{}.{},{}",
+            modified.as_secs(),
+            modified.subsec_nanos(),
+            metadata.len()
+        ))
+    }
+}
+
+/// Files held in memory, directories are implied by the paths of the files
+/// within them, e.g. the fixture database.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub struct MemorySource {
+    files: std::collections::BTreeMap<PathBuf, String>,
+}
+
+#[cfg(test)]
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: &str) -> Self {
+        self.files.insert(path.into(), contents.to_owned());
+        self
+    }
+}
+
+#[cfg(test)]
+impl FileSource for MemorySource {
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|file| file.starts_with(path) && file != path)
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                dir.display().to_string(),
+            ));
+        }
+        let mut entries: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(dir).ok()?.components().next())
+            .map(|entry| dir.join(entry))
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    /// A hash of the contents, files in memory have no modification time.
+    fn stamp(&self, path: &Path) -> Option<String> {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        self.files.get(path)?.hash(&mut hasher);
+        Some(format!("{:x}", hasher.finish()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_source() {
+        let source = MemorySource::new()
+            .file("root/a/one.ini", "1")
+            .file("root/a/two.ini", "2")
+            .file("root/b/c/three.ini", "3");
+        let root = Path::new("root");
+        assert!(source.is_dir(root));
+        assert!(source.is_dir(&root.join("b")));
+        assert!(!source.is_dir(&root.join("a/one.ini")));
+        assert!(source.is_file(&root.join("a/one.ini")));
+        assert_eq!(
+            source.read_dir(root).unwrap(),
+            vec![root.join("a"), root.join("b")]
+        );
+        assert_eq!(
+            source.read_to_string(&root.join("b/c/three.ini")).unwrap(),
+            "3"
+        );
+        assert!(source.read_dir(&root.join("missing")).is_err());
+        assert!(source.read_to_string(&root.join("missing.ini")).is_err());
+    }
+}